@@ -1,4 +1,4 @@
-use std::fmt;
+use std::{fmt, rc::Rc};
 
 use crate::token::Token;
 
@@ -12,7 +12,7 @@ impl fmt::Display for Block {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         self.statements
             .iter()
-            .fold(Ok(()), |_result, stmt| write!(f, "{}", stmt))
+            .try_for_each(|stmt| write!(f, "{}", stmt))
     }
 }
 
@@ -41,6 +41,11 @@ pub enum Stmt {
         body: Block,
         line: usize,
     },
+    RepeatUntil {
+        body: Block,
+        condition: Exp,
+        line: usize,
+    },
     IfStmt {
         condition: Exp,
         then_branch: Block,
@@ -56,17 +61,20 @@ pub enum Stmt {
         body: Block,
         line: usize,
     },
+    // for namelist in explist, calling an iterator function until it returns nil
     GenericFor {
         namelist: NameList,
-        table: Exp,
+        explist: ExpList,
         body: Block,
         line: usize,
     },
+    // line is where 'function' is, the function's definition line
+    // the parameters and body are shared with the functions the declaration makes
     FuncDecl {
         local: bool,
         name: Name,
-        parlist: NameList,
-        body: Block,
+        parlist: Rc<NameList>,
+        body: Rc<Block>,
         line: usize,
     },
     FunctionCall {
@@ -74,6 +82,12 @@ pub enum Stmt {
         arguments: ExpList,
         line: usize,
     },
+    MethodCall {
+        prefixexp: Box<Exp>,
+        name: Name,
+        arguments: ExpList,
+        line: usize,
+    },
     RetStmt {
         explist: ExpList,
         line: usize,
@@ -88,7 +102,7 @@ impl fmt::Display for Stmt {
                 right,
                 line: _,
             } => {
-                write!(f, "{} = {}\n", left, right)
+                writeln!(f, "{} = {}", left, right)
             }
 
             Self::LocalAssign {
@@ -97,9 +111,9 @@ impl fmt::Display for Stmt {
                 line: _,
             } => {
                 if right.0.is_empty() {
-                    write!(f, "local {}\n", left)
+                    writeln!(f, "local {}", left)
                 } else {
-                    write!(f, "local {} = {}\n", left, right)
+                    writeln!(f, "local {} = {}", left, right)
                 }
             }
 
@@ -108,11 +122,20 @@ impl fmt::Display for Stmt {
                 arguments,
                 line: _,
             } => {
-                write!(f, "{}({})\n", prefixexp, arguments)
+                writeln!(f, "{}({})", prefixexp, arguments)
+            }
+
+            Self::MethodCall {
+                prefixexp,
+                name,
+                arguments,
+                line: _,
+            } => {
+                writeln!(f, "{}:{}({})", prefixexp, name, arguments)
             }
 
             Self::Break { line: _ } => {
-                write!(f, "break\n")
+                writeln!(f, "break")
             }
 
             Self::DoBlockEnd { block, line: _ } => {
@@ -154,7 +177,7 @@ impl fmt::Display for Stmt {
                         write!(f, "else{{\n{}}}\n", else_branch)
                     }
                     None => {
-                        write!(f, "\n")
+                        writeln!(f)
                     }
                 }
             }
@@ -167,6 +190,14 @@ impl fmt::Display for Stmt {
                 write!(f, "while({}) {{\n{}}}\n", condition, body)
             }
 
+            Self::RepeatUntil {
+                body,
+                condition,
+                line: _,
+            } => {
+                write!(f, "repeat {{\n{}}} until({})\n", body, condition)
+            }
+
             Self::NumericFor {
                 name,
                 start,
@@ -184,19 +215,19 @@ impl fmt::Display for Stmt {
 
             Self::GenericFor {
                 namelist,
-                table,
+                explist,
                 body,
                 line: _,
             } => {
                 write!(
                     f,
-                    "GenericFor({} = pairs({}) do {{\n{}}}\n",
-                    namelist, table, body
+                    "GenericFor({} = {}) do {{\n{}}}\n",
+                    namelist, explist, body
                 )
             }
 
             Self::RetStmt { explist, line: _ } => {
-                write!(f, "return {}\n", explist)
+                writeln!(f, "return {}", explist)
             }
         }
     }
@@ -205,7 +236,12 @@ impl fmt::Display for Stmt {
 #[derive(Clone)]
 pub enum Var {
     Name { name: Name },
-    TableIndex { prefixexp: Box<Exp>, exp: Box<Exp> },
+    // line is where the '[' or '.' is, used to report errors
+    TableIndex {
+        prefixexp: Box<Exp>,
+        exp: Box<Exp>,
+        line: usize,
+    },
 }
 
 #[derive(Clone)]
@@ -217,7 +253,7 @@ impl fmt::Display for Var {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Self::Name { name } => write!(f, "{}", name),
-            Self::TableIndex { prefixexp, exp } => write!(f, "{}[{}]", prefixexp, exp),
+            Self::TableIndex { prefixexp, exp, .. } => write!(f, "{}[{}]", prefixexp, exp),
         }
     }
 }
@@ -226,15 +262,13 @@ impl fmt::Display for VarList {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let mut count = 0;
         write!(f, "Varlist(")?;
-        self.vars.iter().fold(Ok(()), |result, name| {
-            result.and_then(|_| {
-                count += 1;
-                if count == self.vars.len() {
-                    write!(f, "{})", name)
-                } else {
-                    write!(f, "{}, ", name)
-                }
-            })
+        self.vars.iter().try_for_each(|name| {
+            count += 1;
+            if count == self.vars.len() {
+                write!(f, "{})", name)
+            } else {
+                write!(f, "{}, ", name)
+            }
         })
     }
 }
@@ -249,15 +283,13 @@ impl fmt::Display for NameList {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let mut count = 0;
         write!(f, "Namelist(")?;
-        self.0.iter().fold(Ok(()), |result, name| {
-            result.and_then(|_| {
-                count += 1;
-                if count == self.0.len() {
-                    write!(f, "{})", name)
-                } else {
-                    write!(f, "{}, ", name)
-                }
-            })
+        self.0.iter().try_for_each(|name| {
+            count += 1;
+            if count == self.0.len() {
+                write!(f, "{})", name)
+            } else {
+                write!(f, "{}, ", name)
+            }
         })
     }
 }
@@ -278,16 +310,27 @@ pub enum Exp {
         operator: Token,
         right: Box<Exp>,
     },
+    // line is where 'function' is, the function's definition line
     Function {
         funcbody: FuncBody,
+        line: usize,
     },
     // prefix exp
     Var {
         var: Var,
     },
+    // line is where the call starts, used to report errors
     FunctionCall {
         prefixexp: Box<Exp>,
         arguments: ExpList,
+        line: usize,
+    },
+    // prefixexp:name(arguments), prefixexp is passed as the first argument
+    MethodCall {
+        prefixexp: Box<Exp>,
+        name: Name,
+        arguments: ExpList,
+        line: usize,
     },
     Grouping {
         exp: Box<Exp>,
@@ -295,6 +338,8 @@ pub enum Exp {
     TableConstructor {
         fieldlist: FieldList,
     },
+    // '...', the extra arguments of a variadic function
+    Vararg,
 }
 
 #[derive(Clone)]
@@ -310,18 +355,28 @@ impl fmt::Display for Exp {
                 operator,
                 right,
             } => write!(f, "({} {} {})", left, operator.tok_type, right),
-            Self::Function { funcbody } => write!(f, "{}", funcbody),
+            Self::Function { funcbody, line: _ } => write!(f, "{}", funcbody),
             Self::Var { var } => write!(f, "{}", var),
             Self::FunctionCall {
                 prefixexp,
                 arguments,
+                line: _,
             } => {
                 write!(f, "{}({})", prefixexp, arguments)
             }
+            Self::MethodCall {
+                prefixexp,
+                name,
+                arguments,
+                line: _,
+            } => {
+                write!(f, "{}:{}({})", prefixexp, name, arguments)
+            }
             Self::Grouping { exp } => write!(f, "{}", exp),
             Self::TableConstructor { fieldlist } => {
                 write!(f, "Table{{{}}}", fieldlist)
             }
+            Self::Vararg => write!(f, "..."),
         }
     }
 }
@@ -330,15 +385,13 @@ impl fmt::Display for ExpList {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let mut count = 0;
         write!(f, "ExpList(")?;
-        self.0.iter().fold(Ok(()), |result, name| {
-            result.and_then(|_| {
-                count += 1;
-                if count == self.0.len() {
-                    write!(f, "{})", name)
-                } else {
-                    write!(f, "{}, ", name)
-                }
-            })
+        self.0.iter().try_for_each(|name| {
+            count += 1;
+            if count == self.0.len() {
+                write!(f, "{})", name)
+            } else {
+                write!(f, "{}, ", name)
+            }
         })
     }
 }
@@ -346,8 +399,9 @@ impl fmt::Display for ExpList {
 // funcbody
 #[derive(Clone)]
 pub struct FuncBody {
-    pub parlist: NameList,
-    pub block: Block,
+    // shared with the functions the expression makes, as their parameters and body
+    pub parlist: Rc<NameList>,
+    pub block: Rc<Block>,
 }
 
 impl fmt::Display for FuncBody {
@@ -382,15 +436,13 @@ impl fmt::Display for Field {
 impl fmt::Display for FieldList {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let mut count = 0;
-        self.0.iter().fold(Ok(()), |result, name| {
-            result.and_then(|_| {
-                count += 1;
-                if count == self.0.len() {
-                    write!(f, "{}", name)
-                } else {
-                    write!(f, "{}, ", name)
-                }
-            })
+        self.0.iter().try_for_each(|name| {
+            count += 1;
+            if count == self.0.len() {
+                write!(f, "{}", name)
+            } else {
+                write!(f, "{}, ", name)
+            }
         })
     }
 }
@@ -0,0 +1,2448 @@
+use std::{
+    cell::RefCell,
+    fs,
+    io::{BufRead, Read},
+    rc::Rc,
+    sync::OnceLock,
+    time::{Instant, SystemTime, UNIX_EPOCH},
+};
+
+use ordered_float::OrderedFloat;
+
+use crate::{
+    ast::Block,
+    interpreter::{check_key, Interpreter, RuntimeException},
+    pattern::{self, Capture, Match},
+    rua::{Rua, RuaError},
+    value::{byte_len, float_to_integer, format_g14, HeapObj, NativeFn, Table, Value},
+};
+
+/// build a library table, e.g. `math`, out of its functions and constants
+fn library(lib: &str, functions: &[(&str, NativeFn)], constants: &[(&str, Value)]) -> Table {
+    let mut table = Table::new();
+    for (name, func) in functions {
+        table.insert(
+            Value::Str {
+                value: name.as_bytes().to_vec(),
+            },
+            Value::NativeFunction {
+                name: format!("{}.{}", lib, name),
+                func: *func,
+            },
+        );
+    }
+    for (name, value) in constants {
+        table.insert(
+            Value::Str {
+                value: name.as_bytes().to_vec(),
+            },
+            value.clone(),
+        );
+    }
+
+    table
+}
+
+/// render a value the way tostring() and print() show it,
+/// tables and functions are shown with their type and address,
+/// lua functions also with the line they are defined at
+///
+/// a value whose metatable has a `__tostring` field is shown by calling it,
+/// which must return a string
+///
+/// the result is the bytes of the string, as a lua string may not be UTF-8
+pub fn to_string(interpreter: &mut Interpreter, value: &Value, line: usize) -> Result<Vec<u8>, RuntimeException> {
+    let handler = interpreter.metafield(value, "__tostring");
+    if !matches!(handler, Value::Nil) {
+        return match interpreter.call_function(&handler, vec![value.clone()], line)?.compress() {
+            Value::Str { value } => Ok(value),
+            _ => Err(RuntimeException::new_error(
+                line,
+                String::from("'__tostring' must return a string"),
+            )),
+        };
+    }
+
+    Ok(match value {
+        Value::Str { value } => value.clone(),
+        Value::Address { addr } => {
+            let ty = interpreter.type_of(value);
+            match &*interpreter.dereference(addr).unwrap().borrow() {
+                HeapObj::Function {
+                    parameters: _,
+                    body: _,
+                    closure: _,
+                    def_line,
+                    chunkname: _,
+                } => format!("{}: {} (defined at line {})", ty, addr, def_line).into_bytes(),
+                // shown like the builtins, as host functions are not written in lua either
+                HeapObj::HostFunction { name, func: _ } => {
+                    format!("function: builtin: {}", name).into_bytes()
+                }
+                _ => format!("{}: {}", ty, addr).into_bytes(),
+            }
+        }
+        Value::NativeFunction { name, func: _ } => format!("function: builtin: {}", name).into_bytes(),
+        _ => value.to_string().into_bytes(),
+    })
+}
+
+/// get the i-th (0 based) argument of a builtin as a number,
+/// strings convertible to numbers are accepted as in arithmetic
+fn check_number(
+    interpreter: &Interpreter,
+    arguments: &[Value],
+    i: usize,
+    name: &str,
+    line: usize,
+) -> Result<f64, RuntimeException> {
+    match arguments.get(i) {
+        Some(value) => match value.number() {
+            Some(n) => Ok(n.into_inner()),
+            None => Err(RuntimeException::new_error(
+                line,
+                format!(
+                    "bad argument #{} to '{}' (number expected, got {})",
+                    i + 1,
+                    name,
+                    interpreter.type_of(value)
+                ),
+            )),
+        },
+        None => Err(RuntimeException::new_error(
+            line,
+            format!(
+                "bad argument #{} to '{}' (number expected, got no value)",
+                i + 1,
+                name
+            ),
+        )),
+    }
+}
+
+/// get the i-th (0 based) argument of a builtin as an integer,
+/// `default` is used if it is absent or nil
+fn check_integer(
+    interpreter: &Interpreter,
+    arguments: &[Value],
+    i: usize,
+    name: &str,
+    default: Option<i64>,
+    line: usize,
+) -> Result<i64, RuntimeException> {
+    if let (None | Some(Value::Nil), Some(default)) = (arguments.get(i), default) {
+        return Ok(default);
+    }
+    check_number(interpreter, arguments, i, name, line)?;
+    arguments[i].integer().ok_or_else(|| {
+        RuntimeException::new_error(
+            line,
+            format!(
+                "bad argument #{} to '{}' (number has no integer representation)",
+                i + 1,
+                name
+            ),
+        )
+    })
+}
+
+/// get the i-th (0 based) argument of a builtin as a string, numbers are converted
+fn check_string(
+    interpreter: &Interpreter,
+    arguments: &[Value],
+    i: usize,
+    name: &str,
+    line: usize,
+) -> Result<Vec<u8>, RuntimeException> {
+    let got = match arguments.get(i) {
+        Some(value) => match value.string() {
+            Some(s) => return Ok(s),
+            None => interpreter.type_of(value),
+        },
+        None => String::from("no value"),
+    };
+
+    Err(RuntimeException::new_error(
+        line,
+        format!(
+            "bad argument #{} to '{}' (string expected, got {})",
+            i + 1,
+            name,
+            got
+        ),
+    ))
+}
+
+fn number(n: f64) -> Value {
+    Value::Num {
+        value: OrderedFloat::from(n),
+    }
+}
+
+fn integer(n: i64) -> Value {
+    Value::Int { value: n }
+}
+
+/// a float result that has an integer value is returned as an integer,
+/// as math.floor and math.ceil do
+fn integral(n: f64) -> Value {
+    match float_to_integer(n) {
+        Some(n) => integer(n),
+        None => number(n),
+    }
+}
+
+/// the error of a builtin called without its required argument
+fn value_expected(name: &str, line: usize) -> RuntimeException {
+    RuntimeException::new_error(line, format!("bad argument #1 to '{}' (value expected)", name))
+}
+
+/// print(...), prints the values separated by tabs, then a newline
+pub fn print(
+    interpreter: &mut Interpreter,
+    arguments: Vec<Value>,
+    line: usize,
+) -> Result<Value, RuntimeException> {
+    // print returns no values
+    let nothing = Value::ValueList { values: Vec::new() };
+    if let Some(handler) = interpreter.print_handler() {
+        handler(&arguments);
+        return Ok(nothing);
+    }
+
+    let mut s = Vec::new();
+    for (i, value) in arguments.iter().enumerate() {
+        if i > 0 {
+            s.push(b'\t');
+        }
+        s.extend(to_string(interpreter, value, line)?);
+    }
+    s.push(b'\n');
+    // like Lua, a failed write is not an error of the script
+    let _ = interpreter.output().write_all(&s);
+
+    Ok(nothing)
+}
+
+/// type(v), returns the type of v as a string
+pub fn type_of(
+    interpreter: &mut Interpreter,
+    arguments: Vec<Value>,
+    line: usize,
+) -> Result<Value, RuntimeException> {
+    let ty = match arguments.first() {
+        Some(value) => interpreter.type_of(value),
+        None => return Err(value_expected("type", line)),
+    };
+
+    Ok(str_value(ty))
+}
+
+/// tostring(v), converts a value of any type to a string
+pub fn tostring(
+    interpreter: &mut Interpreter,
+    arguments: Vec<Value>,
+    line: usize,
+) -> Result<Value, RuntimeException> {
+    match arguments.first() {
+        Some(value) => Ok(Value::Str {
+            value: to_string(interpreter, value, line)?,
+        }),
+        None => Err(value_expected("tostring", line)),
+    }
+}
+
+/// tonumber(v [, base]), converts a number or a numeric string to a number,
+/// returns nil if it is not convertible
+///
+/// with a base in [2, 36], v must be a string holding an integer in that base
+pub fn tonumber(
+    _interpreter: &mut Interpreter,
+    arguments: Vec<Value>,
+    line: usize,
+) -> Result<Value, RuntimeException> {
+    let value = match arguments.first() {
+        Some(value) => value,
+        None => return Err(value_expected("tonumber", line)),
+    };
+
+    let base = match arguments.get(1) {
+        None | Some(Value::Nil) => {
+            return Ok(value.to_number().unwrap_or(Value::Nil))
+        }
+        Some(base) => match base.integer() {
+            Some(base) if (2..=36).contains(&base) => base as u32,
+            Some(_) => {
+                return Err(RuntimeException::new_error(
+                    line,
+                    "bad argument #2 to 'tonumber' (base out of range)".to_string(),
+                ))
+            }
+            None => {
+                return Err(RuntimeException::new_error(
+                    line,
+                    format!(
+                        "bad argument #2 to 'tonumber' (number expected, got {})",
+                        base.ty()
+                    ),
+                ))
+            }
+        },
+    };
+
+    let s = match value {
+        Value::Str { value } => value.trim_ascii(),
+        _ => {
+            return Err(RuntimeException::new_error(
+                line,
+                format!(
+                    "bad argument #1 to 'tonumber' (string expected, got {})",
+                    value.ty()
+                ),
+            ))
+        }
+    };
+    let (negative, digits) = match s.strip_prefix(b"-") {
+        Some(digits) => (true, digits),
+        None => (false, s),
+    };
+    if digits.is_empty() {
+        return Ok(Value::Nil);
+    }
+
+    let mut n: i64 = 0;
+    for &c in digits {
+        match (c as char).to_digit(base) {
+            // wraps around on overflow, as lua does
+            Some(d) => n = n.wrapping_mul(base as i64).wrapping_add(d as i64),
+            None => return Ok(Value::Nil),
+        }
+    }
+    if negative {
+        n = n.wrapping_neg();
+    }
+
+    Ok(integer(n))
+}
+
+/// next(table [, index]), returns the (key, value) pair after index in the table,
+/// or nil after the last one, a nil index gets the first pair
+///
+/// the order of the pairs is not specified
+pub fn next(
+    interpreter: &mut Interpreter,
+    arguments: Vec<Value>,
+    line: usize,
+) -> Result<Value, RuntimeException> {
+    let obj = check_table(interpreter, &arguments, 0, "next", line)?;
+    let key = arguments.get(1).cloned().unwrap_or(Value::Nil);
+    let res = match &*obj.borrow() {
+        HeapObj::Table { table, metatable: _ } => table.next(&key),
+        _ => unreachable!(),
+    };
+
+    match res {
+        Some(Some((k, v))) => Ok(Value::ValueList { values: vec![k, v] }),
+        Some(None) => Ok(Value::Nil),
+        None => Err(RuntimeException::new_error(
+            line,
+            String::from("invalid key to 'next'"),
+        )),
+    }
+}
+
+/// pairs(t), returns next, t and nil, so that `for k, v in pairs(t)` visits every pair of t
+pub fn pairs(
+    interpreter: &mut Interpreter,
+    arguments: Vec<Value>,
+    line: usize,
+) -> Result<Value, RuntimeException> {
+    check_table(interpreter, &arguments, 0, "pairs", line)?;
+
+    Ok(Value::ValueList {
+        values: vec![
+            Value::NativeFunction {
+                name: String::from("next"),
+                func: next,
+            },
+            arguments[0].clone(),
+            Value::Nil,
+        ],
+    })
+}
+
+/// error(message [, level]), raises an error with message as the error object,
+/// which may be any value
+///
+/// a string message starts with the position of the error, where level 1 (the default)
+/// is the position error was called at, level 2 the position of the call to the function
+/// that called error, and so on, level 0 leaves the message as it is
+pub fn error(
+    interpreter: &mut Interpreter,
+    arguments: Vec<Value>,
+    line: usize,
+) -> Result<Value, RuntimeException> {
+    let value = arguments.first().cloned().unwrap_or(Value::Nil);
+    let level = check_integer(interpreter, &arguments, 1, "error", Some(1), line)?;
+    let line = match value {
+        Value::Str { value: _ } => interpreter.level_line(level, line),
+        _ => None,
+    };
+
+    Err(RuntimeException::RuntimeError {
+        line,
+        col: None,
+        chunkname: None,
+        value,
+        traceback: Vec::new(),
+    })
+}
+
+/// assert(v [, message, ...]), raises an error if v is false or nil,
+/// otherwise returns all its arguments
+///
+/// message is the error object, it defaults to "assertion failed!"
+pub fn assert(
+    _interpreter: &mut Interpreter,
+    arguments: Vec<Value>,
+    line: usize,
+) -> Result<Value, RuntimeException> {
+    match arguments.first() {
+        Some(v) if v.truthy() => Ok(Value::ValueList { values: arguments }),
+        Some(_) => match arguments.get(1) {
+            // the message is raised as it is, without the position
+            Some(message) => Err(RuntimeException::RuntimeError {
+                line: None,
+                col: None,
+                chunkname: None,
+                value: message.clone(),
+                traceback: Vec::new(),
+            }),
+            None => Err(RuntimeException::new_error(
+                line,
+                String::from("assertion failed!"),
+            )),
+        },
+        None => Err(value_expected("assert", line)),
+    }
+}
+
+/// select(n, ...), the arguments after the n-th one, counting from the end if n is negative,
+/// or select('#', ...), the number of arguments after the first
+pub fn select(
+    interpreter: &mut Interpreter,
+    mut arguments: Vec<Value>,
+    line: usize,
+) -> Result<Value, RuntimeException> {
+    let count = arguments.len().saturating_sub(1) as i64;
+    if let Some(Value::Str { value }) = arguments.first() {
+        if value.starts_with(b"#") {
+            return Ok(integer(count));
+        }
+    }
+
+    let n = check_integer(interpreter, &arguments, 0, "select", None, line)?;
+    let n = if n < 0 { count + n } else { n - 1 };
+    if n < 0 {
+        return Err(RuntimeException::new_error(
+            line,
+            String::from("bad argument #1 to 'select' (index out of range)"),
+        ));
+    }
+    let values = arguments.split_off((n + 1).min(count + 1) as usize);
+    Ok(Value::ValueList { values })
+}
+
+/// pcall(f, ...), calls f with the given arguments in protected mode
+///
+/// returns true and the results of f if it succeeds, or false and the error object
+/// if an error is raised inside it
+pub fn pcall(
+    interpreter: &mut Interpreter,
+    mut arguments: Vec<Value>,
+    line: usize,
+) -> Result<Value, RuntimeException> {
+    if arguments.is_empty() {
+        return Err(value_expected("pcall", line));
+    }
+    let func = arguments.remove(0);
+
+    match interpreter.call_function(&func, arguments, line) {
+        Ok(res) => {
+            let mut values = vec![Value::Bool { b: true }];
+            values.append(&mut res.expand());
+            Ok(Value::ValueList { values })
+        }
+        Err(RuntimeException::RuntimeError {
+            line,
+            chunkname,
+            value,
+            ..
+        }) => Ok(Value::ValueList {
+            values: vec![
+                Value::Bool { b: false },
+                interpreter.error_value(chunkname.as_deref(), line, value),
+            ],
+        }),
+        Err(e) => Err(e),
+    }
+}
+
+/// setmetatable(table, metatable), sets the metatable of table, nil removes it,
+/// returns table
+///
+/// a metatable with a `__metatable` field is protected and cannot be changed
+pub fn setmetatable(
+    interpreter: &mut Interpreter,
+    arguments: Vec<Value>,
+    line: usize,
+) -> Result<Value, RuntimeException> {
+    let obj = check_table(interpreter, &arguments, 0, "setmetatable", line)?;
+    let metatable = match arguments.get(1) {
+        Some(Value::Nil) => None,
+        Some(value @ Value::Address { addr }) if interpreter.type_of(value) == "table" => {
+            Some(addr.clone())
+        }
+        _ => {
+            return Err(RuntimeException::new_error(
+                line,
+                String::from("bad argument #2 to 'setmetatable' (nil or table expected)"),
+            ))
+        }
+    };
+    if !matches!(interpreter.metafield(&arguments[0], "__metatable"), Value::Nil) {
+        return Err(RuntimeException::new_error(
+            line,
+            String::from("cannot change a protected metatable"),
+        ));
+    }
+
+    if let HeapObj::Table {
+        table: _,
+        metatable: old,
+    } = &mut *obj.borrow_mut()
+    {
+        *old = metatable;
+    }
+
+    Ok(arguments[0].clone())
+}
+
+/// getmetatable(object), returns the metatable of object, or nil if it has none
+///
+/// if the metatable has a `__metatable` field, returns its value instead
+pub fn getmetatable(
+    interpreter: &mut Interpreter,
+    arguments: Vec<Value>,
+    line: usize,
+) -> Result<Value, RuntimeException> {
+    let value = match arguments.first() {
+        Some(value) => value,
+        None => return Err(value_expected("getmetatable", line)),
+    };
+
+    match interpreter.get_metatable(value) {
+        Some(metatable) => match interpreter.metafield(value, "__metatable") {
+            Value::Nil => Ok(metatable),
+            protected => Ok(protected),
+        },
+        None => Ok(Value::Nil),
+    }
+}
+
+/// rawget(table, index), gets the real value of table[index], without invoking `__index`
+pub fn rawget(
+    interpreter: &mut Interpreter,
+    arguments: Vec<Value>,
+    line: usize,
+) -> Result<Value, RuntimeException> {
+    let obj = check_table(interpreter, &arguments, 0, "rawget", line)?;
+    let key = match arguments.get(1) {
+        Some(key) => key,
+        None => {
+            return Err(RuntimeException::new_error(
+                line,
+                String::from("bad argument #2 to 'rawget' (value expected)"),
+            ))
+        }
+    };
+
+    if let HeapObj::Table { table, metatable: _ } = &*obj.borrow() {
+        return Ok(table.index(key));
+    }
+
+    Ok(Value::Nil)
+}
+
+/// rawset(table, index, value), sets table[index] to value without invoking `__newindex`,
+/// returns table
+pub fn rawset(
+    interpreter: &mut Interpreter,
+    arguments: Vec<Value>,
+    line: usize,
+) -> Result<Value, RuntimeException> {
+    let obj = check_table(interpreter, &arguments, 0, "rawset", line)?;
+    if arguments.len() < 3 {
+        return Err(RuntimeException::new_error(
+            line,
+            format!("bad argument #{} to 'rawset' (value expected)", arguments.len() + 1),
+        ));
+    }
+    check_key(&arguments[1], line)?;
+
+    if let HeapObj::Table { table, metatable: _ } = &mut *obj.borrow_mut() {
+        table.insert(arguments[1].clone(), arguments[2].clone());
+    }
+
+    Ok(arguments[0].clone())
+}
+
+/// load(chunk [, chunkname]), compiles the string chunk into a function that runs it,
+/// or returns nil and the message if it has a syntax error
+///
+/// errors in the chunk are reported at its lines, in a chunk named after chunkname,
+/// which defaults to chunk itself
+pub fn load(
+    interpreter: &mut Interpreter,
+    arguments: Vec<Value>,
+    line: usize,
+) -> Result<Value, RuntimeException> {
+    let source = check_string(interpreter, &arguments, 0, "load", line)?;
+    let chunkname = match arguments.get(1) {
+        None | Some(Value::Nil) => chunk_id(&source),
+        Some(_) => chunk_id(&check_string(interpreter, &arguments, 1, "load", line)?),
+    };
+
+    match compile(source) {
+        Ok(block) => Ok(interpreter.load(block, &chunkname)),
+        Err(e) => Ok(Value::ValueList {
+            values: vec![Value::Nil, str_value(e.message(&chunkname))],
+        }),
+    }
+}
+
+/// lex and parse the source of a chunk
+fn compile(source: Vec<u8>) -> Result<Block, Box<dyn RuaError>> {
+    let mut program = Rua::new(source);
+    let tokens = program.lex().map_err(|e| Box::new(e) as Box<dyn RuaError>)?;
+    program.parse(tokens).map_err(|e| Box::new(e) as Box<dyn RuaError>)
+}
+
+/// how errors show the name of a chunk, as in lua: a name starting with '=' or '@'
+/// is shown without it, any other is shown as the source, `[string "source"]`,
+/// cut at its first line or at 45 bytes
+fn chunk_id(name: &[u8]) -> String {
+    if let Some(name) = name.strip_prefix(b"=").or_else(|| name.strip_prefix(b"@")) {
+        return String::from_utf8_lossy(name).into_owned();
+    }
+
+    const MAX: usize = 45;
+    let first_line = name.split(|&c| c == b'\n').next().unwrap_or_default();
+    if first_line.len() == name.len() && name.len() < MAX {
+        format!("[string \"{}\"]", String::from_utf8_lossy(name))
+    } else {
+        let cut = &first_line[..first_line.len().min(MAX)];
+        format!("[string \"{}...\"]", String::from_utf8_lossy(cut))
+    }
+}
+
+/// the `package` library: `loaded`, the modules require() has loaded, and `path`,
+/// where require() looks for them
+pub fn package(loaded: Value) -> Table {
+    library(
+        "package",
+        &[],
+        &[("loaded", loaded), ("path", str_value("./?.lua"))],
+    )
+}
+
+/// require(modname), loads the module modname once, and returns package.loaded[modname]
+///
+/// the module is the first file found by replacing each '?' in the templates of
+/// package.path, separated by ';', with modname, whose dots are turned into '/'. it is run
+/// with modname and the file name, and its result is stored in package.loaded[modname];
+/// a module returning nothing, or nil, is stored as true. a second require of the same
+/// module returns the stored value, unless it is false, in which case the module runs again
+pub fn require(
+    interpreter: &mut Interpreter,
+    arguments: Vec<Value>,
+    line: usize,
+) -> Result<Value, RuntimeException> {
+    let name = check_string(interpreter, &arguments, 0, "require", line)?;
+    let package = interpreter.package();
+    let loaded = interpreter.index(&package, &str_value("loaded"), line)?;
+    let key = str_value(name.clone());
+    let value = interpreter.index(&loaded, &key, line)?;
+    if value.truthy() {
+        return Ok(value);
+    }
+
+    let name = String::from_utf8_lossy(&name).into_owned();
+    let path = match interpreter.index(&package, &str_value("path"), line)? {
+        Value::Str { value } => String::from_utf8_lossy(&value).into_owned(),
+        _ => {
+            return Err(RuntimeException::new_error(
+                line,
+                String::from("'package.path' must be a string"),
+            ))
+        }
+    };
+    let mut tried = String::new();
+    let mut found = None;
+    for template in path.split(';') {
+        let filename = template.replace('?', &name.replace('.', "/"));
+        match fs::read(&filename) {
+            Ok(source) => {
+                found = Some((filename, source));
+                break;
+            }
+            Err(_) => tried.push_str(&format!("\n\tno file '{}'", filename)),
+        }
+    }
+    let (filename, source) = match found {
+        Some(found) => found,
+        None => {
+            return Err(RuntimeException::new_error(
+                line,
+                format!("module '{}' not found:{}", name, tried),
+            ))
+        }
+    };
+
+    let block = compile(source).map_err(|e| {
+        RuntimeException::new_error(
+            line,
+            format!(
+                "error loading module '{}' from file '{}':\n\t{}",
+                name,
+                filename,
+                e.message(&filename)
+            ),
+        )
+    })?;
+    let module = interpreter.load(block, &filename);
+    let arguments = vec![key.clone(), str_value(filename.as_str())];
+    let value = interpreter.call_function(&module, arguments, line)?.compress();
+
+    // the module may have stored its value itself, which nil does not replace
+    let obj = match &loaded {
+        Value::Address { addr } => interpreter.dereference(addr).unwrap(),
+        _ => {
+            return Err(RuntimeException::new_error(
+                line,
+                String::from("'package.loaded' must be a table"),
+            ))
+        }
+    };
+    if let HeapObj::Table { table, metatable: _ } = &mut *obj.borrow_mut() {
+        if !matches!(value, Value::Nil) {
+            table.insert(key.clone(), value);
+        }
+        if matches!(table.index(&key), Value::Nil) {
+            table.insert(key.clone(), Value::Bool { b: true });
+        }
+    }
+    let value = match &*obj.borrow() {
+        HeapObj::Table { table, metatable: _ } => table.index(&key),
+        _ => unreachable!(),
+    };
+
+    // the file name is given back too, as lua does
+    Ok(Value::ValueList {
+        values: vec![value, str_value(filename)],
+    })
+}
+
+/// ipairs(t), returns an iterator function, t and 0, so that `for i, v in ipairs(t)`
+/// visits (1, t[1]), (2, t[2]), ... up to the first nil value
+pub fn ipairs(
+    interpreter: &mut Interpreter,
+    arguments: Vec<Value>,
+    line: usize,
+) -> Result<Value, RuntimeException> {
+    check_table(interpreter, &arguments, 0, "ipairs", line)?;
+
+    Ok(Value::ValueList {
+        values: vec![
+            Value::NativeFunction {
+                name: String::from("ipairs_iterator"),
+                func: ipairs_iterator,
+            },
+            arguments[0].clone(),
+            integer(0),
+        ],
+    })
+}
+
+/// the iterator of ipairs, returns i + 1 and t[i + 1], or nil if t[i + 1] is nil
+fn ipairs_iterator(
+    interpreter: &mut Interpreter,
+    arguments: Vec<Value>,
+    line: usize,
+) -> Result<Value, RuntimeException> {
+    let i = check_integer(interpreter, &arguments, 1, "ipairs_iterator", None, line)? + 1;
+    let table = arguments.first().cloned().unwrap_or(Value::Nil);
+    match interpreter.index(&table, &integer(i), line)?.compress() {
+        Value::Nil => Ok(Value::Nil),
+        value => Ok(Value::ValueList {
+            values: vec![integer(i), value],
+        }),
+    }
+}
+
+/// the `math` library
+pub fn math() -> Table {
+    library(
+        "math",
+        &[
+            ("floor", math_floor),
+            ("ceil", math_ceil),
+            ("abs", math_abs),
+            ("sqrt", math_sqrt),
+            ("max", math_max),
+            ("min", math_min),
+            ("sin", math_sin),
+            ("cos", math_cos),
+            ("tan", math_tan),
+            ("exp", math_exp),
+            ("log", math_log),
+        ],
+        &[("pi", number(std::f64::consts::PI)), ("huge", number(f64::INFINITY))],
+    )
+}
+
+/// math.floor(x), the largest integral value less than or equal to x
+fn math_floor(
+    interpreter: &mut Interpreter,
+    arguments: Vec<Value>,
+    line: usize,
+) -> Result<Value, RuntimeException> {
+    if let Some(Value::Int { value }) = arguments.first().and_then(|n| n.to_number()) {
+        return Ok(integer(value));
+    }
+    Ok(integral(check_number(interpreter, &arguments, 0, "floor", line)?.floor()))
+}
+
+/// math.ceil(x), the smallest integral value greater than or equal to x
+fn math_ceil(
+    interpreter: &mut Interpreter,
+    arguments: Vec<Value>,
+    line: usize,
+) -> Result<Value, RuntimeException> {
+    if let Some(Value::Int { value }) = arguments.first().and_then(|n| n.to_number()) {
+        return Ok(integer(value));
+    }
+    // adding 0 turns the -0 of math.ceil(-0.5) into 0
+    Ok(integral(check_number(interpreter, &arguments, 0, "ceil", line)?.ceil() + 0.0))
+}
+
+/// math.abs(x), math.abs(-0.0) is 0.0 and math.abs(nan) is nan
+fn math_abs(
+    interpreter: &mut Interpreter,
+    arguments: Vec<Value>,
+    line: usize,
+) -> Result<Value, RuntimeException> {
+    if let Some(Value::Int { value }) = arguments.first().and_then(|n| n.to_number()) {
+        return Ok(integer(value.wrapping_abs()));
+    }
+    Ok(number(check_number(interpreter, &arguments, 0, "abs", line)?.abs()))
+}
+
+/// math.sqrt(x), negative numbers give nan instead of an error
+fn math_sqrt(
+    interpreter: &mut Interpreter,
+    arguments: Vec<Value>,
+    line: usize,
+) -> Result<Value, RuntimeException> {
+    Ok(number(check_number(interpreter, &arguments, 0, "sqrt", line)?.sqrt()))
+}
+
+/// math.max(x, ...), the argument with the maximum value
+fn math_max(
+    interpreter: &mut Interpreter,
+    arguments: Vec<Value>,
+    line: usize,
+) -> Result<Value, RuntimeException> {
+    let mut max = check_number(interpreter, &arguments, 0, "max", line)?;
+    // the argument itself is returned, so an integer stays an integer
+    let mut res = 0;
+    for i in 1..arguments.len() {
+        let n = check_number(interpreter, &arguments, i, "max", line)?;
+        if max < n {
+            max = n;
+            res = i;
+        }
+    }
+
+    Ok(arguments[res].to_number().unwrap())
+}
+
+/// math.min(x, ...), the argument with the minimum value
+fn math_min(
+    interpreter: &mut Interpreter,
+    arguments: Vec<Value>,
+    line: usize,
+) -> Result<Value, RuntimeException> {
+    let mut min = check_number(interpreter, &arguments, 0, "min", line)?;
+    // the argument itself is returned, so an integer stays an integer
+    let mut res = 0;
+    for i in 1..arguments.len() {
+        let n = check_number(interpreter, &arguments, i, "min", line)?;
+        if n < min {
+            min = n;
+            res = i;
+        }
+    }
+
+    Ok(arguments[res].to_number().unwrap())
+}
+
+/// math.sin(x), x in radians
+fn math_sin(
+    interpreter: &mut Interpreter,
+    arguments: Vec<Value>,
+    line: usize,
+) -> Result<Value, RuntimeException> {
+    Ok(number(check_number(interpreter, &arguments, 0, "sin", line)?.sin()))
+}
+
+/// math.cos(x), x in radians
+fn math_cos(
+    interpreter: &mut Interpreter,
+    arguments: Vec<Value>,
+    line: usize,
+) -> Result<Value, RuntimeException> {
+    Ok(number(check_number(interpreter, &arguments, 0, "cos", line)?.cos()))
+}
+
+/// math.tan(x), x in radians
+fn math_tan(
+    interpreter: &mut Interpreter,
+    arguments: Vec<Value>,
+    line: usize,
+) -> Result<Value, RuntimeException> {
+    Ok(number(check_number(interpreter, &arguments, 0, "tan", line)?.tan()))
+}
+
+/// math.exp(x), e raised to the power x
+fn math_exp(
+    interpreter: &mut Interpreter,
+    arguments: Vec<Value>,
+    line: usize,
+) -> Result<Value, RuntimeException> {
+    Ok(number(check_number(interpreter, &arguments, 0, "exp", line)?.exp()))
+}
+
+/// math.log(x [, base]), the natural logarithm by default
+///
+/// out of the domain there is no error: math.log(0) is -inf and math.log(-1) is nan
+fn math_log(
+    interpreter: &mut Interpreter,
+    arguments: Vec<Value>,
+    line: usize,
+) -> Result<Value, RuntimeException> {
+    let x = check_number(interpreter, &arguments, 0, "log", line)?;
+    let res = match arguments.get(1) {
+        None | Some(Value::Nil) => x.ln(),
+        Some(_) => {
+            // bases 2 and 10 are exact, as in math.log(8, 2) == 3
+            match check_number(interpreter, &arguments, 1, "log", line)? {
+                2.0 => x.log2(),
+                10.0 => x.log10(),
+                base => x.ln() / base.ln(),
+            }
+        }
+    };
+
+    Ok(number(res))
+}
+
+/// the `string` library
+///
+/// strings are indexed by bytes, the first byte is at position 1
+pub fn string() -> Table {
+    library(
+        "string",
+        &[
+            ("len", string_len),
+            ("sub", string_sub),
+            ("upper", string_upper),
+            ("lower", string_lower),
+            ("rep", string_rep),
+            ("reverse", string_reverse),
+            ("format", string_format),
+            ("gsub", string_gsub),
+            ("gmatch", string_gmatch),
+        ],
+        &[],
+    )
+}
+
+/// a string value from its text or bytes
+fn str_value(value: impl Into<Vec<u8>>) -> Value {
+    Value::Str {
+        value: value.into(),
+    }
+}
+
+/// string.len(s), the number of bytes in s
+fn string_len(
+    interpreter: &mut Interpreter,
+    arguments: Vec<Value>,
+    line: usize,
+) -> Result<Value, RuntimeException> {
+    let s = check_string(interpreter, &arguments, 0, "len", line)?;
+    Ok(integer(byte_len(&s)))
+}
+
+/// string.sub(s [, i [, j]]), the bytes of s from i to j (inclusive)
+///
+/// negative indices count from the end of s, -1 is the last byte,
+/// and indices out of the string are clamped to it
+fn string_sub(
+    interpreter: &mut Interpreter,
+    arguments: Vec<Value>,
+    line: usize,
+) -> Result<Value, RuntimeException> {
+    let s = check_string(interpreter, &arguments, 0, "sub", line)?;
+    let i = check_integer(interpreter, &arguments, 1, "sub", Some(1), line)?;
+    let j = check_integer(interpreter, &arguments, 2, "sub", Some(-1), line)?;
+
+    let len = s.len() as i64;
+    let start = match i {
+        i if i > 0 => i,
+        i if i == 0 || i < -len => 1,
+        i => len + i + 1,
+    };
+    let end = match j {
+        j if j > len => len,
+        j if j >= 0 => j,
+        j if j < -len => 0,
+        j => len + j + 1,
+    };
+
+    if start > end {
+        return Ok(str_value(Vec::new()));
+    }
+    Ok(str_value(&s[start as usize - 1..end as usize]))
+}
+
+/// string.upper(s), only ascii letters are changed
+fn string_upper(
+    interpreter: &mut Interpreter,
+    arguments: Vec<Value>,
+    line: usize,
+) -> Result<Value, RuntimeException> {
+    let s = check_string(interpreter, &arguments, 0, "upper", line)?;
+    Ok(str_value(s.to_ascii_uppercase()))
+}
+
+/// string.lower(s), only ascii letters are changed
+fn string_lower(
+    interpreter: &mut Interpreter,
+    arguments: Vec<Value>,
+    line: usize,
+) -> Result<Value, RuntimeException> {
+    let s = check_string(interpreter, &arguments, 0, "lower", line)?;
+    Ok(str_value(s.to_ascii_lowercase()))
+}
+
+/// string.rep(s, n [, sep]), n copies of s separated by sep
+fn string_rep(
+    interpreter: &mut Interpreter,
+    arguments: Vec<Value>,
+    line: usize,
+) -> Result<Value, RuntimeException> {
+    let s = check_string(interpreter, &arguments, 0, "rep", line)?;
+    let n = check_integer(interpreter, &arguments, 1, "rep", None, line)?;
+    let sep = match arguments.get(2) {
+        None | Some(Value::Nil) => Vec::new(),
+        Some(_) => check_string(interpreter, &arguments, 2, "rep", line)?,
+    };
+
+    if n <= 0 {
+        return Ok(str_value(Vec::new()));
+    }
+    Ok(str_value(vec![s; n as usize].join(&sep[..])))
+}
+
+/// string.reverse(s), s with its bytes in reverse order
+fn string_reverse(
+    interpreter: &mut Interpreter,
+    arguments: Vec<Value>,
+    line: usize,
+) -> Result<Value, RuntimeException> {
+    let mut s = check_string(interpreter, &arguments, 0, "reverse", line)?;
+    s.reverse();
+    Ok(str_value(s))
+}
+
+/// a conversion specification of string.format, `%[flags][width][.precision]conversion`
+struct FormatSpec {
+    left: bool,
+    plus: bool,
+    space: bool,
+    alt: bool,
+    zero: bool,
+    width: usize,
+    precision: Option<usize>,
+    conversion: char,
+}
+
+impl FormatSpec {
+    /// parse the specification after a '%', returns it with the number of bytes it takes
+    fn parse(fmt: &[u8], line: usize) -> Result<(Self, usize), RuntimeException> {
+        let mut spec = Self {
+            left: false,
+            plus: false,
+            space: false,
+            alt: false,
+            zero: false,
+            width: 0,
+            precision: None,
+            conversion: '\0',
+        };
+
+        let mut i = 0;
+        while i < fmt.len() {
+            match fmt[i] {
+                b'-' => spec.left = true,
+                b'+' => spec.plus = true,
+                b' ' => spec.space = true,
+                b'#' => spec.alt = true,
+                b'0' => spec.zero = true,
+                _ => break,
+            }
+            i += 1;
+        }
+
+        // width and precision have at most 2 digits, as in Lua
+        let digits = |i: &mut usize| {
+            let begin = *i;
+            while *i < fmt.len() && fmt[*i].is_ascii_digit() && *i - begin < 2 {
+                *i += 1;
+            }
+            String::from_utf8_lossy(&fmt[begin..*i]).parse::<usize>().unwrap_or(0)
+        };
+        spec.width = digits(&mut i);
+        if i < fmt.len() && fmt[i] == b'.' {
+            i += 1;
+            spec.precision = Some(digits(&mut i));
+        }
+
+        match fmt.get(i) {
+            Some(c) if c.is_ascii_alphabetic() || *c == b'%' => {
+                spec.conversion = *c as char;
+                Ok((spec, i + 1))
+            }
+            _ => Err(RuntimeException::new_error(
+                line,
+                format!(
+                    "invalid conversion '%{}' to 'format'",
+                    String::from_utf8_lossy(&fmt[..(i + 1).min(fmt.len())])
+                ),
+            )),
+        }
+    }
+
+    /// the sign of a number, following the '+' and ' ' flags
+    fn sign(&self, negative: bool) -> &'static str {
+        if negative {
+            "-"
+        } else if self.plus {
+            "+"
+        } else if self.space {
+            " "
+        } else {
+            ""
+        }
+    }
+
+    /// pad a formatted number to the width, zeros go between the sign (and prefix) and the digits
+    fn pad_number(&self, prefix: &str, digits: &str, zero: bool) -> String {
+        let len = prefix.len() + digits.len();
+        if len >= self.width {
+            format!("{}{}", prefix, digits)
+        } else if self.left {
+            format!("{}{}{}", prefix, digits, " ".repeat(self.width - len))
+        } else if zero {
+            format!("{}{}{}", prefix, "0".repeat(self.width - len), digits)
+        } else {
+            format!("{}{}{}", " ".repeat(self.width - len), prefix, digits)
+        }
+    }
+
+    /// pad a string to the width with spaces
+    fn pad(&self, s: &[u8]) -> Vec<u8> {
+        let fill = vec![b' '; self.width.saturating_sub(s.len())];
+        if self.left {
+            [s, &fill].concat()
+        } else {
+            [&fill, s].concat()
+        }
+    }
+
+    /// format an integer for the d, i, u, c, o, x and X conversions
+    fn integer(&self, n: i64) -> String {
+        let (prefix, mut digits) = match self.conversion {
+            // negative numbers are shown in two's complement
+            'o' => (if self.alt { "0" } else { "" }.to_string(), format!("{:o}", n)),
+            'x' => (if self.alt { "0x" } else { "" }.to_string(), format!("{:x}", n)),
+            'X' => (if self.alt { "0X" } else { "" }.to_string(), format!("{:X}", n)),
+            _ => (
+                self.sign(n < 0).to_string(),
+                n.unsigned_abs().to_string(),
+            ),
+        };
+        // the precision is the minimum number of digits
+        if let Some(precision) = self.precision {
+            if digits.len() < precision {
+                digits = format!("{}{}", "0".repeat(precision - digits.len()), digits);
+            }
+        }
+
+        self.pad_number(&prefix, &digits, self.zero && self.precision.is_none())
+    }
+
+    /// format a float for the f, F, e and E conversions
+    fn float(&self, n: f64) -> String {
+        let sign = self.sign(n.is_sign_negative() && !n.is_nan());
+        if !n.is_finite() {
+            let s = if n.is_nan() { "nan" } else { "inf" };
+            let s = if self.conversion.is_ascii_uppercase() {
+                s.to_ascii_uppercase()
+            } else {
+                s.to_string()
+            };
+            return self.pad_number(sign, &s, false);
+        }
+
+        let precision = self.precision.unwrap_or(6);
+        let digits = match self.conversion {
+            'e' | 'E' => {
+                // rust writes 1.5e2, C writes 1.5e+02
+                let s = format!("{:.*e}", precision, n.abs());
+                let (mantissa, exponent) = s.split_once('e').unwrap();
+                let exponent: i32 = exponent.parse().unwrap();
+                let e = if self.conversion == 'e' { 'e' } else { 'E' };
+                let exp_sign = if exponent < 0 { '-' } else { '+' };
+                format!("{}{}{}{:02}", mantissa, e, exp_sign, exponent.abs())
+            }
+            _ => format!("{:.*}", precision, n.abs()),
+        };
+        let digits = if self.alt && !digits.contains('.') {
+            // '#' always writes the decimal point
+            match digits.find(['e', 'E']) {
+                Some(pos) => format!("{}.{}", &digits[..pos], &digits[pos..]),
+                None => format!("{}.", digits),
+            }
+        } else {
+            digits
+        };
+
+        self.pad_number(sign, &digits, self.zero)
+    }
+}
+
+/// the hexadecimal form of a float, like C's "%a": 0x1.8p+1 is 3
+fn hex_float(n: f64) -> String {
+    let sign = if n.is_sign_negative() { "-" } else { "" };
+    if n == 0.0 {
+        return format!("{}0x0p+0", sign);
+    }
+
+    let bits = n.to_bits();
+    let biased = ((bits >> 52) & 0x7ff) as i64;
+    let mantissa = bits & ((1 << 52) - 1);
+    // subnormal numbers have no implicit leading 1
+    let (lead, exponent) = if biased == 0 {
+        (0, -1022)
+    } else {
+        (1, biased - 1023)
+    };
+    let fraction = format!("{:013x}", mantissa);
+    let fraction = fraction.trim_end_matches('0');
+    if fraction.is_empty() {
+        format!("{}0x{}p{:+}", sign, lead, exponent)
+    } else {
+        format!("{}0x{}.{}p{:+}", sign, lead, fraction, exponent)
+    }
+}
+
+/// the `%q` form of a value, which reads back as the same value
+///
+/// `arg` is the position of the value in the arguments of string.format, for the error message
+fn quote(value: &Value, arg: usize, line: usize) -> Result<Vec<u8>, RuntimeException> {
+    match value {
+        Value::Str { value } => {
+            let mut s = vec![b'"'];
+            for (i, &c) in value.iter().enumerate() {
+                let next_is_digit = value.get(i + 1).is_some_and(|b| b.is_ascii_digit());
+                match c {
+                    b'"' => s.extend_from_slice(b"\\\""),
+                    b'\\' => s.extend_from_slice(b"\\\\"),
+                    b'\n' => s.extend_from_slice(b"\\\n"),
+                    b'\r' => s.extend_from_slice(b"\\r"),
+                    // a digit after the escape would be read as part of it
+                    c if c.is_ascii_control() && next_is_digit => {
+                        s.extend(format!("\\{:03}", c).into_bytes())
+                    }
+                    c if c.is_ascii_control() => s.extend(format!("\\{}", c).into_bytes()),
+                    c => s.push(c),
+                }
+            }
+            s.push(b'"');
+            Ok(s)
+        }
+        Value::Int { value: n } => Ok(n.to_string().into_bytes()),
+        // floats are written in hexadecimal, which keeps every bit and reads back as a float
+        Value::Num { value: n } => {
+            let n = n.into_inner();
+            Ok(if n.is_nan() {
+                String::from("(0/0)")
+            } else if n == f64::INFINITY {
+                String::from("1e9999")
+            } else if n == f64::NEG_INFINITY {
+                String::from("-1e9999")
+            } else {
+                hex_float(n)
+            }
+            .into_bytes())
+        }
+        Value::Nil | Value::Bool { b: _ } => Ok(value.to_string().into_bytes()),
+        _ => Err(RuntimeException::new_error(
+            line,
+            format!("bad argument #{} to 'format' (value has no literal form)", arg),
+        )),
+    }
+}
+
+/// string.format(formatstring, ...), formats the values following the printf-style formatstring
+///
+/// supports the conversions d, i, u, c, o, x, X, e, E, f, F, s, q and %%
+fn string_format(
+    interpreter: &mut Interpreter,
+    arguments: Vec<Value>,
+    line: usize,
+) -> Result<Value, RuntimeException> {
+    let fmt = check_string(interpreter, &arguments, 0, "format", line)?;
+
+    let mut res: Vec<u8> = Vec::new();
+    let mut arg = 0;
+    let mut i = 0;
+    while i < fmt.len() {
+        if fmt[i] != b'%' {
+            res.push(fmt[i]);
+            i += 1;
+            continue;
+        }
+
+        let (spec, n) = FormatSpec::parse(&fmt[i + 1..], line)?;
+        let spec_str = String::from_utf8_lossy(&fmt[i..i + 1 + n]).into_owned();
+        i += 1 + n;
+        if spec.conversion == '%' {
+            res.push(b'%');
+            continue;
+        }
+
+        arg += 1;
+        if arg >= arguments.len() {
+            return Err(RuntimeException::new_error(
+                line,
+                format!("bad argument #{} to 'format' (no value)", arg + 1),
+            ));
+        }
+        let s = match spec.conversion {
+            'd' | 'i' | 'u' | 'c' | 'o' | 'x' | 'X' => {
+                // floats are accepted if they have an integer value, e.g. 3.0
+                let n = check_integer(interpreter, &arguments, arg, "format", None, line)?;
+                if spec.conversion == 'c' {
+                    spec.pad(&[n as u8])
+                } else {
+                    spec.integer(n).into_bytes()
+                }
+            }
+            'e' | 'E' | 'f' | 'F' => {
+                let n = check_number(interpreter, &arguments, arg, "format", line)?;
+                spec.float(n).into_bytes()
+            }
+            's' => {
+                let s = to_string(interpreter, &arguments[arg], line)?;
+                match spec.precision {
+                    Some(precision) if precision < s.len() => {
+                        spec.pad(&s[..precision])
+                    }
+                    _ => spec.pad(&s),
+                }
+            }
+            'q' => {
+                if spec_str.len() > 2 {
+                    return Err(RuntimeException::new_error(
+                        line,
+                        String::from("specifier '%q' cannot have modifiers"),
+                    ));
+                }
+                quote(&arguments[arg], arg + 1, line)?
+            }
+            _ => {
+                return Err(RuntimeException::new_error(
+                    line,
+                    format!("invalid conversion '{}' to 'format'", spec_str),
+                ))
+            }
+        };
+        res.extend(s);
+    }
+
+    Ok(str_value(res))
+}
+
+/// the value of a capture, a string or a position
+fn capture_value(capture: &Capture) -> Value {
+    match capture {
+        Capture::Str(s) => str_value(s.to_vec()),
+        Capture::Position(p) => integer(*p as i64),
+    }
+}
+
+/// expand a replacement string of gsub, '%0' is the whole match,
+/// '%1' to '%9' are the captures and '%%' is a '%'
+fn expand_replacement(
+    repl: &[u8],
+    m: &Match,
+    whole: &[u8],
+    line: usize,
+) -> Result<Vec<u8>, RuntimeException> {
+    let mut res = Vec::new();
+    let mut i = 0;
+    while i < repl.len() {
+        if repl[i] != b'%' {
+            res.push(repl[i]);
+            i += 1;
+            continue;
+        }
+
+        i += 1;
+        match repl.get(i) {
+            Some(b'%') => res.push(b'%'),
+            Some(b'0') => res.extend_from_slice(whole),
+            Some(d @ b'1'..=b'9') => match m.captures.get((d - b'1') as usize) {
+                Some(Capture::Str(s)) => res.extend_from_slice(s),
+                Some(Capture::Position(p)) => res.extend_from_slice(p.to_string().as_bytes()),
+                None => {
+                    return Err(RuntimeException::new_error(
+                        line,
+                        format!("invalid capture index %{} in replacement string", *d as char),
+                    ))
+                }
+            },
+            _ => {
+                return Err(RuntimeException::new_error(
+                    line,
+                    String::from("invalid use of '%' in replacement string"),
+                ))
+            }
+        }
+        i += 1;
+    }
+
+    Ok(res)
+}
+
+/// string.gsub(s, pattern, repl [, n]), replaces the first n (all by default)
+/// matches of pattern in s, returns the new string and the number of replacements
+///
+/// repl may be a string, a table indexed by the first capture or a function called
+/// with the captures, a nil or false result keeps the match unchanged
+fn string_gsub(
+    interpreter: &mut Interpreter,
+    arguments: Vec<Value>,
+    line: usize,
+) -> Result<Value, RuntimeException> {
+    let s = check_string(interpreter, &arguments, 0, "gsub", line)?;
+    let pattern = check_string(interpreter, &arguments, 1, "gsub", line)?;
+    let repl = arguments.get(2).cloned().unwrap_or(Value::Nil);
+    let repl_ty = interpreter.type_of(&repl);
+    if !matches!(repl_ty.as_str(), "string" | "number" | "table" | "function") {
+        return Err(RuntimeException::new_error(
+            line,
+            format!(
+                "bad argument #3 to 'gsub' (string/function/table expected, got {})",
+                if arguments.len() > 2 { repl_ty.as_str() } else { "no value" }
+            ),
+        ));
+    }
+    let max_n = check_integer(interpreter, &arguments, 3, "gsub", Some(s.len() as i64 + 1), line)?;
+
+    let src = &s[..];
+    // '^' anchors the pattern at the start of s, so it replaces at most once
+    let (anchor, pat) = match pattern.strip_prefix(b"^") {
+        Some(pat) => (true, pat),
+        None => (false, &pattern[..]),
+    };
+
+    let mut res: Vec<u8> = Vec::new();
+    let mut pos = 0;
+    // an empty match right after the previous match is skipped
+    let mut last_match = None;
+    let mut n = 0;
+    while n < max_n {
+        let m = pattern::match_at(src, pat, pos).map_err(|e| RuntimeException::new_error(line, e))?;
+        match m {
+            Some(m) if Some(m.end) != last_match => {
+                n += 1;
+                let whole = &src[m.start..m.end];
+                let value = match repl_ty.as_str() {
+                    "table" => interpreter.index(&repl, &capture_value(&m.captures[0]), line)?,
+                    "function" => {
+                        let captures = m.captures.iter().map(capture_value).collect();
+                        interpreter.call_function(&repl, captures, line)?.compress()
+                    }
+                    _ => {
+                        let repl = repl.string().unwrap();
+                        str_value(expand_replacement(&repl, &m, whole, line)?)
+                    }
+                };
+                match value {
+                    Value::Nil | Value::Bool { b: false } => res.extend_from_slice(whole),
+                    value => match value.string() {
+                        Some(s) => res.extend(s),
+                        None => {
+                            return Err(RuntimeException::new_error(
+                                line,
+                                format!(
+                                    "invalid replacement value (a {})",
+                                    interpreter.type_of(&value)
+                                ),
+                            ))
+                        }
+                    },
+                }
+                pos = m.end;
+                last_match = Some(m.end);
+            }
+            _ if pos < src.len() => {
+                res.push(src[pos]);
+                pos += 1;
+            }
+            _ => break,
+        }
+        if anchor {
+            break;
+        }
+    }
+    res.extend_from_slice(&src[pos..]);
+
+    Ok(Value::ValueList {
+        values: vec![str_value(res), integer(n)],
+    })
+}
+
+/// string.gmatch(s, pattern), returns an iterator function that
+/// gives the captures of the next match of pattern in s on each call
+fn string_gmatch(
+    interpreter: &mut Interpreter,
+    arguments: Vec<Value>,
+    line: usize,
+) -> Result<Value, RuntimeException> {
+    let s = check_string(interpreter, &arguments, 0, "gmatch", line)?;
+    let pattern = check_string(interpreter, &arguments, 1, "gmatch", line)?;
+
+    let addr = interpreter.alloc(HeapObj::NativeClosure {
+        func: gmatch_next,
+        // the subject, the pattern, where to search next and the end of the last match
+        upvalues: vec![str_value(s), str_value(pattern), integer(0), Value::Nil],
+    });
+
+    Ok(Value::Address { addr })
+}
+
+/// the iterator returned by string.gmatch, returns nil after the last match
+fn gmatch_next(
+    _interpreter: &mut Interpreter,
+    upvalues: &mut [Value],
+    _arguments: Vec<Value>,
+    line: usize,
+) -> Result<Value, RuntimeException> {
+    let s = upvalues[0].string().unwrap();
+    let pattern = upvalues[1].string().unwrap();
+    let (src, pat) = (&s[..], &pattern[..]);
+    let last_match = upvalues[3].number().map(|n| n.into_inner() as usize);
+
+    let mut pos = upvalues[2].number().unwrap().into_inner() as usize;
+    while pos <= src.len() {
+        let m = pattern::match_at(src, pat, pos).map_err(|e| RuntimeException::new_error(line, e))?;
+        if let Some(m) = m {
+            if Some(m.end) != last_match {
+                upvalues[2] = integer(m.end as i64);
+                upvalues[3] = integer(m.end as i64);
+                return Ok(Value::ValueList {
+                    values: m.captures.iter().map(capture_value).collect(),
+                });
+            }
+        }
+        pos += 1;
+    }
+
+    // no more matches
+    upvalues[2] = integer(pos as i64);
+    Ok(Value::Nil)
+}
+
+/// the `table` library
+///
+/// the functions work on the sequence part of a table, from 1 to its border
+pub fn table() -> Table {
+    library(
+        "table",
+        &[
+            ("insert", table_insert),
+            ("remove", table_remove),
+            ("concat", table_concat),
+            ("pack", table_pack),
+            ("unpack", table_unpack),
+            ("sort", table_sort),
+        ],
+        &[],
+    )
+}
+
+/// get the i-th (0 based) argument of a builtin as a table, returns the table's object
+fn check_table(
+    interpreter: &Interpreter,
+    arguments: &[Value],
+    i: usize,
+    name: &str,
+    line: usize,
+) -> Result<Rc<RefCell<HeapObj>>, RuntimeException> {
+    let got = match arguments.get(i) {
+        Some(value) => {
+            if let Value::Address { addr } = value {
+                let obj = interpreter.dereference(addr).unwrap();
+                if let HeapObj::Table { table: _, metatable: _ } = &*obj.clone().borrow() {
+                    return Ok(obj);
+                }
+            }
+            interpreter.type_of(value)
+        }
+        None => String::from("no value"),
+    };
+
+    Err(RuntimeException::new_error(
+        line,
+        format!(
+            "bad argument #{} to '{}' (table expected, got {})",
+            i + 1,
+            name,
+            got
+        ),
+    ))
+}
+
+fn position_out_of_bounds(name: &str, line: usize) -> RuntimeException {
+    RuntimeException::new_error(
+        line,
+        format!("bad argument #2 to '{}' (position out of bounds)", name),
+    )
+}
+
+/// table.insert(t, [pos,] value), inserts value at position pos of t,
+/// shifting up the elements after it, pos defaults to #t + 1 (appending)
+fn table_insert(
+    interpreter: &mut Interpreter,
+    arguments: Vec<Value>,
+    line: usize,
+) -> Result<Value, RuntimeException> {
+    let obj = check_table(interpreter, &arguments, 0, "insert", line)?;
+    let (pos, value) = match arguments.len() {
+        2 => (None, arguments[1].clone()),
+        3 => {
+            let pos = check_integer(interpreter, &arguments, 1, "insert", None, line)?;
+            (Some(pos), arguments[2].clone())
+        }
+        _ => {
+            return Err(RuntimeException::new_error(
+                line,
+                String::from("wrong number of arguments to 'insert'"),
+            ))
+        }
+    };
+
+    let mut obj = obj.borrow_mut();
+    let table = match &mut *obj {
+        HeapObj::Table { table, metatable: _ } => table,
+        _ => unreachable!(),
+    };
+    let end = table.border() as i64 + 1;
+    let pos = match pos {
+        Some(pos) if pos < 1 || pos > end => return Err(position_out_of_bounds("insert", line)),
+        Some(pos) => pos,
+        None => end,
+    };
+
+    for i in (pos + 1..=end).rev() {
+        let moved = table.index(&integer(i - 1));
+        table.insert(integer(i), moved);
+    }
+    table.insert(integer(pos as i64), value);
+
+    Ok(Value::Nil)
+}
+
+/// table.remove(t [, pos]), removes and returns the element at position pos of t,
+/// shifting down the elements after it, pos defaults to #t (the last element)
+fn table_remove(
+    interpreter: &mut Interpreter,
+    arguments: Vec<Value>,
+    line: usize,
+) -> Result<Value, RuntimeException> {
+    let obj = check_table(interpreter, &arguments, 0, "remove", line)?;
+    let pos = match arguments.get(1) {
+        None | Some(Value::Nil) => None,
+        Some(_) => Some(check_integer(interpreter, &arguments, 1, "remove", None, line)?),
+    };
+
+    let mut obj = obj.borrow_mut();
+    let table = match &mut *obj {
+        HeapObj::Table { table, metatable: _ } => table,
+        _ => unreachable!(),
+    };
+    let size = table.border() as i64;
+    let mut pos = pos.unwrap_or(size);
+    // #t + 1 is accepted as well, removing nothing
+    if pos != size && (pos < 1 || pos > size + 1) {
+        return Err(position_out_of_bounds("remove", line));
+    }
+
+    let removed = table.index(&integer(pos as i64));
+    while pos < size {
+        let moved = table.index(&integer((pos + 1) as i64));
+        table.insert(integer(pos as i64), moved);
+        pos += 1;
+    }
+    table.insert(integer(pos as i64), Value::Nil);
+
+    Ok(removed)
+}
+
+/// table.concat(t [, sep [, i [, j]]]), joins the strings or numbers t[i], ..., t[j]
+/// with sep between them, i defaults to 1 and j to #t
+fn table_concat(
+    interpreter: &mut Interpreter,
+    arguments: Vec<Value>,
+    line: usize,
+) -> Result<Value, RuntimeException> {
+    let obj = check_table(interpreter, &arguments, 0, "concat", line)?;
+    let sep = match arguments.get(1) {
+        None | Some(Value::Nil) => Vec::new(),
+        Some(_) => check_string(interpreter, &arguments, 1, "concat", line)?,
+    };
+    let i = check_integer(interpreter, &arguments, 2, "concat", Some(1), line)?;
+    let j = match arguments.get(3) {
+        None | Some(Value::Nil) => None,
+        Some(_) => Some(check_integer(interpreter, &arguments, 3, "concat", None, line)?),
+    };
+
+    let obj = obj.borrow();
+    let table = match &*obj {
+        HeapObj::Table { table, metatable: _ } => table,
+        _ => unreachable!(),
+    };
+    let j = j.unwrap_or(table.border() as i64);
+
+    let mut parts = Vec::new();
+    for k in i..=j {
+        match table.index(&integer(k)).string() {
+            Some(s) => parts.push(s),
+            None => {
+                return Err(RuntimeException::new_error(
+                    line,
+                    format!("invalid value (at index {}) in table for 'concat'", k),
+                ))
+            }
+        }
+    }
+
+    Ok(str_value(parts.join(&sep[..])))
+}
+
+/// table.pack(...), a new table with the arguments at keys 1, ..., n and the field n
+/// holding their number, so trailing nils are counted too
+fn table_pack(
+    interpreter: &mut Interpreter,
+    arguments: Vec<Value>,
+    _line: usize,
+) -> Result<Value, RuntimeException> {
+    let mut table = Table::new();
+    let n = arguments.len() as i64;
+    for (k, value) in (1..).zip(arguments) {
+        if !matches!(value, Value::Nil) {
+            table.insert(integer(k), value);
+        }
+    }
+    table.insert(str_value(String::from("n")), integer(n));
+
+    let addr = interpreter.alloc(HeapObj::Table {
+        table,
+        metatable: None,
+    });
+    Ok(Value::Address { addr })
+}
+
+/// table.unpack(t [, i [, j]]), returns t[i], ..., t[j], i defaults to 1 and j to #t
+fn table_unpack(
+    interpreter: &mut Interpreter,
+    arguments: Vec<Value>,
+    line: usize,
+) -> Result<Value, RuntimeException> {
+    let obj = check_table(interpreter, &arguments, 0, "unpack", line)?;
+    let i = check_integer(interpreter, &arguments, 1, "unpack", Some(1), line)?;
+    let j = match arguments.get(2) {
+        None | Some(Value::Nil) => None,
+        Some(_) => Some(check_integer(interpreter, &arguments, 2, "unpack", None, line)?),
+    };
+
+    let obj = obj.borrow();
+    let table = match &*obj {
+        HeapObj::Table { table, metatable: _ } => table,
+        _ => unreachable!(),
+    };
+    let j = j.unwrap_or(table.border() as i64);
+    if i > j {
+        return Ok(Value::ValueList { values: Vec::new() });
+    }
+    // the same limit as the stack of lua
+    if j.checked_sub(i).is_none_or(|n| n >= 1_000_000) {
+        return Err(RuntimeException::new_error(
+            line,
+            String::from("too many results to unpack"),
+        ));
+    }
+
+    let values = (i..=j).map(|k| table.index(&integer(k))).collect();
+    Ok(Value::ValueList { values })
+}
+
+/// table.sort(t [, comp]), sorts t[1], ..., t[#t] in place, by comp(a, b) telling if a
+/// goes before b, or by '<' if comp is not given. the sort is not stable
+///
+/// a comp that is not a strict order, e.g. one that is true for both (a, b) and (b, a),
+/// may raise "invalid order function for sorting"
+fn table_sort(
+    interpreter: &mut Interpreter,
+    arguments: Vec<Value>,
+    line: usize,
+) -> Result<Value, RuntimeException> {
+    let obj = check_table(interpreter, &arguments, 0, "sort", line)?;
+    let comp = arguments.get(1).cloned().unwrap_or(Value::Nil);
+    if !matches!(comp, Value::Nil) && interpreter.type_of(&comp) != "function" {
+        return Err(RuntimeException::new_error(
+            line,
+            format!(
+                "bad argument #2 to 'sort' (function expected, got {})",
+                interpreter.type_of(&comp)
+            ),
+        ));
+    }
+
+    // the elements are sorted out of the table, as comp may read it
+    let mut values: Vec<Value> = match &*obj.borrow() {
+        HeapObj::Table { table, metatable: _ } => {
+            (1..=table.border() as i64).map(|k| table.index(&integer(k))).collect()
+        }
+        _ => unreachable!(),
+    };
+    if values.len() > 1 {
+        let up = values.len() - 1;
+        sort_range(interpreter, &mut values, 0, up, &comp, line)?;
+    }
+
+    if let HeapObj::Table { table, metatable: _ } = &mut *obj.borrow_mut() {
+        for (k, value) in values.into_iter().enumerate() {
+            table.insert(integer(k as i64 + 1), value);
+        }
+    }
+    Ok(Value::ValueList { values: Vec::new() })
+}
+
+/// whether a goes before b for table.sort, by comp or by '<' if comp is nil
+fn sort_less(
+    interpreter: &mut Interpreter,
+    comp: &Value,
+    a: &Value,
+    b: &Value,
+    line: usize,
+) -> Result<bool, RuntimeException> {
+    let res = match comp {
+        Value::Nil => interpreter.less(a, b, line)?,
+        comp => interpreter.call_function(comp, vec![a.clone(), b.clone()], line)?,
+    };
+    Ok(res.compress().truthy())
+}
+
+fn invalid_order(line: usize) -> RuntimeException {
+    RuntimeException::new_error(line, String::from("invalid order function for sorting"))
+}
+
+/// sort values[lo..=up] with the quicksort of lua, which notices a comp that is not an order
+/// when the partition runs past the pivot
+fn sort_range(
+    interpreter: &mut Interpreter,
+    values: &mut [Value],
+    mut lo: usize,
+    mut up: usize,
+    comp: &Value,
+    line: usize,
+) -> Result<(), RuntimeException> {
+    while lo < up {
+        // order the first, middle and last elements, the middle one becomes the pivot
+        if sort_less(interpreter, comp, &values[up], &values[lo], line)? {
+            values.swap(lo, up);
+        }
+        if up - lo == 1 {
+            break;
+        }
+        let p = lo + (up - lo) / 2;
+        if sort_less(interpreter, comp, &values[p], &values[lo], line)? {
+            values.swap(p, lo);
+        } else if sort_less(interpreter, comp, &values[up], &values[p], line)? {
+            values.swap(p, up);
+        }
+        if up - lo == 2 {
+            break;
+        }
+
+        // partition around the pivot, kept at up - 1 until it goes to its place p
+        let pivot = values[p].clone();
+        values.swap(p, up - 1);
+        let (mut i, mut j) = (lo, up - 1);
+        let p = loop {
+            i += 1;
+            while sort_less(interpreter, comp, &values[i], &pivot, line)? {
+                // values[i] < pivot, but values[up - 1] is the pivot
+                if i == up - 1 {
+                    return Err(invalid_order(line));
+                }
+                i += 1;
+            }
+            j -= 1;
+            while sort_less(interpreter, comp, &pivot, &values[j], line)? {
+                // pivot < values[j], but values[j] is before values[i], which is not below pivot
+                if j < i {
+                    return Err(invalid_order(line));
+                }
+                j -= 1;
+            }
+            if j < i {
+                values.swap(up - 1, i);
+                break i;
+            }
+            values.swap(i, j);
+        };
+
+        // recurse into the smaller part and loop on the larger one
+        if p - lo < up - p {
+            sort_range(interpreter, values, lo, p - 1, comp, line)?;
+            lo = p + 1;
+        } else {
+            sort_range(interpreter, values, p + 1, up, comp, line)?;
+            up = p - 1;
+        }
+    }
+    Ok(())
+}
+
+/// the `io` library, writing to the same output as print() and reading stdin,
+/// or the input the host gives
+pub fn io() -> Table {
+    library("io", &[("write", io_write), ("read", io_read)], &[])
+}
+
+/// io.write(...), writes its arguments, strings or numbers, one after another
+/// with nothing between them; floats are written with "%.14g"
+fn io_write(
+    interpreter: &mut Interpreter,
+    arguments: Vec<Value>,
+    line: usize,
+) -> Result<Value, RuntimeException> {
+    let mut s = Vec::new();
+    for (i, value) in arguments.iter().enumerate() {
+        match value {
+            Value::Str { value } => s.extend_from_slice(value),
+            Value::Int { value } => s.extend(value.to_string().into_bytes()),
+            Value::Num { value } => s.extend(format_g14(value.into_inner()).into_bytes()),
+            _ => {
+                return Err(RuntimeException::new_error(
+                    line,
+                    format!(
+                        "bad argument #{} to 'write' (string expected, got {})",
+                        i + 1,
+                        interpreter.type_of(value)
+                    ),
+                ))
+            }
+        }
+    }
+    // like print, a failed write is not an error of the script
+    let _ = interpreter.output().write_all(&s);
+
+    Ok(Value::ValueList { values: Vec::new() })
+}
+
+/// io.read(···), reads the input following each format, and returns a value for each:
+/// "l" a line without its newline, "L" a line keeping it, "n" a number, "a" all the rest,
+/// or a count of bytes. the formats may start with '*', the default is "l"
+///
+/// nothing read gives nil, and the formats after it are not read, but "a" gives ""
+fn io_read(
+    interpreter: &mut Interpreter,
+    arguments: Vec<Value>,
+    line: usize,
+) -> Result<Value, RuntimeException> {
+    let formats = if arguments.is_empty() {
+        vec![str_value(String::from("l"))]
+    } else {
+        arguments
+    };
+
+    let mut values = Vec::new();
+    for (i, format) in formats.iter().enumerate() {
+        let format = match format {
+            Value::Str { value } => value.iter().find(|&&c| c != b'*').map(|&c| c as char),
+            _ => None,
+        };
+        let value = match format {
+            Some('l') => interpreter.with_input(|input| read_line(input, false)),
+            Some('L') => interpreter.with_input(|input| read_line(input, true)),
+            Some('n') => interpreter.with_input(read_number),
+            Some('a') => interpreter.with_input(read_all),
+            _ if formats[i].to_number().is_some() => {
+                let count = check_integer(interpreter, &formats, i, "read", None, line)?;
+                interpreter.with_input(|input| read_count(input, count.max(0) as usize))
+            }
+            _ => {
+                return Err(RuntimeException::new_error(
+                    line,
+                    format!("bad argument #{} to 'read' (invalid format)", i + 1),
+                ))
+            }
+        };
+        // nothing more is read after the end of the input
+        let end = matches!(value, Value::Nil);
+        values.push(value);
+        if end {
+            break;
+        }
+    }
+
+    Ok(Value::ValueList { values })
+}
+
+/// read a line, nil at the end of the input
+fn read_line(input: &mut dyn BufRead, keep_newline: bool) -> Value {
+    let mut bytes = Vec::new();
+    match input.read_until(b'\n', &mut bytes) {
+        Ok(0) | Err(_) => Value::Nil,
+        Ok(_) => {
+            if !keep_newline && bytes.last() == Some(&b'\n') {
+                bytes.pop();
+            }
+            str_value(bytes)
+        }
+    }
+}
+
+/// read all the rest of the input, "" at its end
+fn read_all(input: &mut dyn BufRead) -> Value {
+    let mut bytes = Vec::new();
+    let _ = input.read_to_end(&mut bytes);
+    str_value(bytes)
+}
+
+/// read up to `count` bytes, nil at the end of the input
+fn read_count(input: &mut dyn BufRead, count: usize) -> Value {
+    let mut bytes = Vec::new();
+    let _ = input.take(count as u64).read_to_end(&mut bytes);
+    // reading 0 bytes tells whether the input has ended
+    let ended = match input.fill_buf() {
+        Ok(rest) => rest.is_empty(),
+        Err(_) => true,
+    };
+    if bytes.is_empty() && (count > 0 || ended) {
+        Value::Nil
+    } else {
+        str_value(bytes)
+    }
+}
+
+/// read a numeral after any whitespace, nil if what follows is not a number
+///
+/// only the bytes that can continue the numeral are read, as Lua does
+fn read_number(input: &mut dyn BufRead) -> Value {
+    // read the next byte into the numeral if `pred` accepts it
+    fn accept(input: &mut dyn BufRead, numeral: &mut Vec<u8>, pred: impl Fn(u8) -> bool) -> bool {
+        match input.fill_buf() {
+            Ok([c, ..]) if pred(*c) => {
+                numeral.push(*c);
+                input.consume(1);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    while let Ok([c, ..]) = input.fill_buf() {
+        if !c.is_ascii_whitespace() {
+            break;
+        }
+        input.consume(1);
+    }
+
+    let mut numeral = Vec::new();
+    accept(input, &mut numeral, |c| matches!(c, b'+' | b'-'));
+    let mut hex = false;
+    if accept(input, &mut numeral, |c| c == b'0') {
+        hex = accept(input, &mut numeral, |c| matches!(c, b'x' | b'X'));
+    }
+    let digit = |c: u8| if hex { c.is_ascii_hexdigit() } else { c.is_ascii_digit() };
+    while accept(input, &mut numeral, digit) {}
+    if accept(input, &mut numeral, |c| c == b'.') {
+        while accept(input, &mut numeral, digit) {}
+    }
+    let exponent: &[u8] = if hex { b"pP" } else { b"eE" };
+    if accept(input, &mut numeral, |c| exponent.contains(&c)) {
+        accept(input, &mut numeral, |c| matches!(c, b'+' | b'-'));
+        while accept(input, &mut numeral, |c| c.is_ascii_digit()) {}
+    }
+
+    str_value(String::from_utf8_lossy(&numeral).into_owned())
+        .to_number()
+        .unwrap_or(Value::Nil)
+}
+
+/// when the program started, as os.clock() measures from it
+static START: OnceLock<Instant> = OnceLock::new();
+
+/// the `os` library, for dates and times
+///
+/// there is no time zone support, dates are always in UTC
+pub fn os() -> Table {
+    START.get_or_init(Instant::now);
+    library(
+        "os",
+        &[
+            ("time", os_time),
+            ("clock", os_clock),
+            ("date", os_date),
+            ("getenv", os_getenv),
+            ("exit", os_exit),
+        ],
+        &[],
+    )
+}
+
+/// os.time([t]), the current time as a number of seconds since the epoch,
+/// or the time of the date in table t, with the fields year, month, day,
+/// and the optional hour (12 by default), min and sec
+fn os_time(
+    interpreter: &mut Interpreter,
+    arguments: Vec<Value>,
+    line: usize,
+) -> Result<Value, RuntimeException> {
+    if let None | Some(Value::Nil) = arguments.first() {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs() as i64);
+        return Ok(integer(now));
+    }
+
+    let obj = check_table(interpreter, &arguments, 0, "time", line)?;
+    let obj = obj.borrow();
+    let table = match &*obj {
+        HeapObj::Table {
+            table,
+            metatable: _,
+        } => table,
+        _ => unreachable!(),
+    };
+    let field = |key: &str, default: Option<i64>| {
+        match table.index(&str_value(key.to_string())) {
+            Value::Nil => default.ok_or_else(|| format!("field '{}' missing in date table", key)),
+            value => value
+                .integer()
+                .ok_or_else(|| format!("field '{}' is not an integer", key)),
+        }
+        .map_err(|message| RuntimeException::new_error(line, message))
+    };
+    let (year, month, day) = (
+        field("year", None)?,
+        field("month", None)?,
+        field("day", None)?,
+    );
+    let (hour, min, sec) = (
+        field("hour", Some(12))?,
+        field("min", Some(0))?,
+        field("sec", Some(0))?,
+    );
+
+    // months out of 1..=12 carry over into the year, other fields just add up
+    let out_of_bound = |key: &str| {
+        RuntimeException::new_error(line, format!("field '{}' is out-of-bound", key))
+    };
+    let month = month.checked_sub(1).ok_or_else(|| out_of_bound("month"))?;
+    let (year, month) = (
+        year.checked_add(month.div_euclid(12))
+            .ok_or_else(|| out_of_bound("year"))?,
+        month.rem_euclid(12) + 1,
+    );
+    let days = days_from_civil(year, month, 1).ok_or_else(|| out_of_bound("year"))?;
+    let time = days
+        .checked_add(day)
+        .and_then(|days| days.checked_sub(1))
+        .ok_or_else(|| out_of_bound("day"))?
+        .checked_mul(86400)
+        .ok_or_else(|| out_of_bound("day"))?;
+    [(hour, 3600, "hour"), (min, 60, "min"), (sec, 1, "sec")]
+        .into_iter()
+        .try_fold(time, |time, (value, secs, key)| {
+            value
+                .checked_mul(secs)
+                .and_then(|secs| time.checked_add(secs))
+                .ok_or_else(|| out_of_bound(key))
+        })
+        .map(integer)
+}
+
+/// os.clock(), the time in seconds the program has been running, as a float
+fn os_clock(
+    _interpreter: &mut Interpreter,
+    _arguments: Vec<Value>,
+    _line: usize,
+) -> Result<Value, RuntimeException> {
+    Ok(number(
+        START.get_or_init(Instant::now).elapsed().as_secs_f64(),
+    ))
+}
+
+/// os.date([format [, time]]), the date of time (now by default) as a string following format,
+/// whose conversions are those of C's strftime, "%c" by default. the format "*t"
+/// gives a table with the fields year, month, day, hour, min, sec, wday, yday and isdst.
+/// a leading '!' (UTC) is accepted and ignored, as all dates are in UTC
+fn os_date(
+    interpreter: &mut Interpreter,
+    arguments: Vec<Value>,
+    line: usize,
+) -> Result<Value, RuntimeException> {
+    let format = match arguments.first() {
+        None | Some(Value::Nil) => b"%c".to_vec(),
+        Some(_) => check_string(interpreter, &arguments, 0, "date", line)?,
+    };
+    let time = match arguments.get(1) {
+        None | Some(Value::Nil) => os_time(interpreter, Vec::new(), line)?.integer().unwrap(),
+        Some(_) => check_integer(interpreter, &arguments, 1, "date", None, line)?,
+    };
+    let format = format.strip_prefix(b"!").unwrap_or(&format);
+    let date = Date::new(time);
+
+    if format.starts_with(b"*t") {
+        let mut table = Table::new();
+        for (key, value) in [
+            ("year", integer(date.year)),
+            ("month", integer(date.month)),
+            ("day", integer(date.day)),
+            ("hour", integer(date.hour)),
+            ("min", integer(date.min)),
+            ("sec", integer(date.sec)),
+            ("wday", integer(date.wday)),
+            ("yday", integer(date.yday)),
+            ("isdst", Value::Bool { b: false }),
+        ] {
+            table.insert(str_value(key.to_string()), value);
+        }
+        let addr = interpreter.alloc(HeapObj::Table {
+            table,
+            metatable: None,
+        });
+        return Ok(Value::Address { addr });
+    }
+
+    let mut s = Vec::new();
+    let mut bytes = format.iter();
+    while let Some(&c) = bytes.next() {
+        if c != b'%' {
+            s.push(c);
+            continue;
+        }
+        let conversion = bytes.next().map(|&c| c as char);
+        match conversion.and_then(|c| date.conversion(c)) {
+            Some(converted) => s.extend(converted.into_bytes()),
+            None => {
+                return Err(RuntimeException::new_error(
+                    line,
+                    format!(
+                        "bad argument #1 to 'date' (invalid conversion specifier '%{}')",
+                        conversion.map_or(String::new(), String::from)
+                    ),
+                ))
+            }
+        }
+    }
+
+    Ok(str_value(s))
+}
+
+/// os.getenv(varname), the value of the environment variable, or nil if it is not set
+fn os_getenv(
+    interpreter: &mut Interpreter,
+    arguments: Vec<Value>,
+    line: usize,
+) -> Result<Value, RuntimeException> {
+    let name = check_string(interpreter, &arguments, 0, "getenv", line)?;
+    let name = String::from_utf8_lossy(&name).into_owned();
+    Ok(std::env::var(name).map_or(Value::Nil, str_value))
+}
+
+/// os.exit([code]), ends the process with the status code, 0 by default.
+/// true is 0 and false is 1. the output is flushed first
+fn os_exit(
+    interpreter: &mut Interpreter,
+    arguments: Vec<Value>,
+    line: usize,
+) -> Result<Value, RuntimeException> {
+    let code = match arguments.first() {
+        Some(Value::Bool { b }) => i64::from(!b),
+        _ => check_integer(interpreter, &arguments, 0, "exit", Some(0), line)?,
+    };
+    // nothing can be reported now if flushing fails
+    let _ = interpreter.output().flush();
+    std::process::exit(code as i32)
+}
+
+/// the days from 1970-01-01 to the date, for the proleptic gregorian calendar,
+/// `None` if they do not fit in an integer
+fn days_from_civil(year: i64, month: i64, day: i64) -> Option<i64> {
+    // count from March, so the leap day is the last of the year
+    let year = if month <= 2 { year.checked_sub(1)? } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year.rem_euclid(400);
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era.checked_mul(146097)?
+        .checked_add(day_of_era - 719468)
+}
+
+/// a time broken down into the fields of its date, in UTC
+struct Date {
+    year: i64,
+    month: i64,
+    day: i64,
+    hour: i64,
+    min: i64,
+    sec: i64,
+    // the day of the week, sunday is 1
+    wday: i64,
+    // the day of the year, january 1st is 1
+    yday: i64,
+}
+
+impl Date {
+    fn new(time: i64) -> Self {
+        let (days, secs) = (time.div_euclid(86400), time.rem_euclid(86400));
+
+        // the inverse of days_from_civil
+        let z = days + 719468;
+        let era = z.div_euclid(146097);
+        let day_of_era = z.rem_euclid(146097);
+        let year_of_era =
+            (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let mp = (5 * day_of_year + 2) / 153;
+        let day = day_of_year - (153 * mp + 2) / 5 + 1;
+        let month = if mp < 10 { mp + 3 } else { mp - 9 };
+        let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+        Date {
+            year,
+            month,
+            day,
+            hour: secs / 3600,
+            min: secs / 60 % 60,
+            sec: secs % 60,
+            // 1970-01-01 was a thursday
+            wday: (days + 4).rem_euclid(7) + 1,
+            // the year of a time is small enough for its days to fit
+            yday: days - days_from_civil(year, 1, 1).unwrap() + 1,
+        }
+    }
+
+    /// the text of a strftime conversion, like 'Y' for "%Y", `None` if it is not supported
+    fn conversion(&self, c: char) -> Option<String> {
+        const DAYS: [&str; 7] = [
+            "Sunday",
+            "Monday",
+            "Tuesday",
+            "Wednesday",
+            "Thursday",
+            "Friday",
+            "Saturday",
+        ];
+        const MONTHS: [&str; 12] = [
+            "January",
+            "February",
+            "March",
+            "April",
+            "May",
+            "June",
+            "July",
+            "August",
+            "September",
+            "October",
+            "November",
+            "December",
+        ];
+        let day_name = DAYS[self.wday as usize - 1];
+        let month_name = MONTHS[self.month as usize - 1];
+
+        Some(match c {
+            'Y' => self.year.to_string(),
+            'y' => format!("{:02}", self.year.rem_euclid(100)),
+            'm' => format!("{:02}", self.month),
+            'd' => format!("{:02}", self.day),
+            'e' => format!("{:2}", self.day),
+            'H' => format!("{:02}", self.hour),
+            'I' => format!("{:02}", (self.hour + 11) % 12 + 1),
+            'M' => format!("{:02}", self.min),
+            'S' => format!("{:02}", self.sec),
+            'p' => String::from(if self.hour < 12 { "AM" } else { "PM" }),
+            'j' => format!("{:03}", self.yday),
+            'a' => day_name[..3].to_string(),
+            'A' => day_name.to_string(),
+            'b' | 'h' => month_name[..3].to_string(),
+            'B' => month_name.to_string(),
+            'w' => (self.wday - 1).to_string(),
+            'D' | 'x' => format!(
+                "{:02}/{:02}/{:02}",
+                self.month,
+                self.day,
+                self.year.rem_euclid(100)
+            ),
+            'F' => format!("{}-{:02}-{:02}", self.year, self.month, self.day),
+            'T' | 'X' => format!("{:02}:{:02}:{:02}", self.hour, self.min, self.sec),
+            'R' => format!("{:02}:{:02}", self.hour, self.min),
+            'c' => format!(
+                "{} {} {:2} {:02}:{:02}:{:02} {}",
+                &day_name[..3],
+                &month_name[..3],
+                self.day,
+                self.hour,
+                self.min,
+                self.sec,
+                self.year
+            ),
+            'n' => String::from("\n"),
+            't' => String::from("\t"),
+            '%' => String::from("%"),
+            _ => return None,
+        })
+    }
+}
@@ -1,12 +1,22 @@
+use std::cell::RefCell;
 use std::cmp::Eq;
 use std::collections::HashMap;
 use std::fmt;
+use std::rc::Rc;
 
-use crate::value::Value;
+use crate::{
+    builtin,
+    value::{NativeFn, Value},
+};
 
+/// a scope of variables
+///
+/// every variable lives in a shared cell: a closure keeps a copy of the environments
+/// it is defined in, holding the same cells, so it sees (and makes) the changes to the
+/// variables it captures. variables declared after the closure are not in its copy
 #[derive(Clone)]
 pub struct Environment {
-    table: HashMap<String, Value>,
+    table: HashMap<String, Rc<RefCell<Value>>>,
 }
 
 impl Environment {
@@ -19,23 +29,73 @@ impl Environment {
     /// the global environment for the whole program
     pub fn global_env() -> Self {
         Self {
-            table: HashMap::from([(String::from("print"), Value::Print)]),
+            table: HashMap::from([
+                native("print", builtin::print),
+                native("type", builtin::type_of),
+                native("tostring", builtin::tostring),
+                native("tonumber", builtin::tonumber),
+                native("next", builtin::next),
+                native("pairs", builtin::pairs),
+                native("ipairs", builtin::ipairs),
+                native("error", builtin::error),
+                native("assert", builtin::assert),
+                native("pcall", builtin::pcall),
+                native("select", builtin::select),
+                native("setmetatable", builtin::setmetatable),
+                native("getmetatable", builtin::getmetatable),
+                native("rawget", builtin::rawget),
+                native("rawset", builtin::rawset),
+                native("load", builtin::load),
+                native("require", builtin::require),
+            ])
+            .into_iter()
+            .map(|(name, value)| (name, Rc::new(RefCell::new(value))))
+            .collect(),
         }
     }
 
-    /// defines a new variable or update an existing variable
+    /// defines a new variable, in a new cell, so the closures holding an earlier
+    /// variable of the same name keep it
     pub fn define(&mut self, name: &str, value: Value) {
-        self.table.insert(name.to_string(), value);
+        self.table.insert(name.to_string(), Rc::new(RefCell::new(value)));
+    }
+
+    /// update an existing variable, or define it if it does not exist
+    pub fn assign(&mut self, name: &str, value: Value) {
+        match self.table.get(name) {
+            Some(cell) => *cell.borrow_mut() = value,
+            None => self.define(name, value),
+        }
     }
 
     /// get variable value
-    pub fn get(&self, name: &str) -> Option<&Value> {
-        self.table.get(name)
+    pub fn get(&self, name: &str) -> Option<Value> {
+        self.table.get(name).map(|cell| cell.borrow().clone())
     }
 
     pub fn contain(&self, name: &str) -> bool {
         self.table.contains_key(name)
     }
+
+    /// whether both environments hold the same variable `name`, the same cell,
+    /// as an environment and the copy of it a closure keeps do
+    pub fn shares(&self, other: &Environment, name: &str) -> bool {
+        match (self.table.get(name), other.table.get(name)) {
+            (Some(cell), Some(other)) => Rc::ptr_eq(cell, other),
+            _ => false,
+        }
+    }
+}
+
+/// a (name, builtin function) entry of the global environment
+fn native(name: &str, func: NativeFn) -> (String, Value) {
+    (
+        name.to_string(),
+        Value::NativeFunction {
+            name: name.to_string(),
+            func,
+        },
+    )
 }
 
 #[derive(PartialEq, Hash, Clone)]
@@ -1,21 +1,62 @@
-use std::collections::HashMap;
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    io::{self, BufRead, Write},
+    rc::Rc,
+};
 
 use ordered_float::OrderedFloat;
 
 use crate::{
     ast::{Block, Exp, ExpList, FieldList, FuncBody, Name, NameList, Stmt, Var, VarList},
+    builtin,
     environment::{Address, Environment},
     rua::RuaError,
     token::{Token, TokenType},
-    value::{HeapObj, Table, Value},
+    value::{byte_len, float_to_integer, HeapObj, PrintHandler, Table, Value},
 };
 
+// the longest chain of __index tables followed by a lookup
+const MAX_INDEX_CHAIN: usize = 2000;
+
+/// a call to a lua function that is running
+#[derive(Clone)]
+pub struct CallFrame {
+    // how the call names the function, like "function 'foo'", None if it does not
+    name: Option<String>,
+    // the line of the call
+    line: usize,
+    // the line the function is defined at, which tells an unnamed function
+    def_line: usize,
+    // the chunk the function is defined in
+    chunkname: Rc<str>,
+}
+
 pub struct Interpreter {
     env_stack: Vec<Environment>,
-    addr_space: HashMap<Address, HeapObj>,
+    addr_space: HashMap<Address, Rc<RefCell<HeapObj>>>,
     cur_addr: usize,
+    // the string library, where indexing a string looks up its methods
+    string_lib: Value,
+    // the package library, where require() finds the path of modules and the loaded ones,
+    // kept aside like string_lib
+    package: Value,
+    // the name of the running chunk, the script or one given to load(),
+    // errors raised with a position start with it
+    chunkname: Rc<str>,
+    // the running lua functions, the innermost one last
+    call_stack: Vec<CallFrame>,
+    // replaces what print() does, set by the host embedding the interpreter
+    print_handler: Option<Box<PrintHandler>>,
+    // where print() writes, stdout unless the host sets another sink
+    output: Box<dyn Write>,
+    // where io.read() reads, stdin if the host sets no other source
+    input: Option<Box<dyn BufRead>>,
 }
 
+/// the most lua functions running at once, a deeper recursion is an error
+const MAX_CALLS: usize = 500;
+
 impl Interpreter {
     /// push an environment onto the stack
     fn push_env(&mut self, env: Environment, line: usize) -> Result<(), RuntimeException> {
@@ -23,7 +64,7 @@ impl Interpreter {
         if self.env_stack.len() >= 1000 {
             Err(RuntimeException::new_error(
                 line,
-                format!("exceeds the maximum stack sizes"),
+                "exceeds the maximum stack sizes".to_string(),
             ))
         } else {
             Ok(())
@@ -43,14 +84,14 @@ impl Interpreter {
     fn define_global(&mut self, name: &Name, value: Value) {
         for index in (0..self.env_stack.len()).rev() {
             if self.env_stack[index].contain(name) || index == 0 {
-                self.env_stack[index].define(name, value);
+                self.env_stack[index].assign(name, value);
                 break;
             }
         }
     }
 
     /// get variable value
-    fn get(&self, name: &Name) -> Option<&Value> {
+    fn get(&self, name: &Name) -> Option<Value> {
         let n = self.env_stack.len();
         for index in (0..n).rev() {
             if let Some(val) = self.env_stack[index].get(name) {
@@ -61,24 +102,130 @@ impl Interpreter {
         None
     }
 
+    /// the metatable of a value, only tables have one
+    pub fn get_metatable(&self, value: &Value) -> Option<Value> {
+        if let Value::Address { addr } = value {
+            if let HeapObj::Table {
+                table: _,
+                metatable: Some(metatable),
+            } = &*self.dereference(addr).unwrap().borrow()
+            {
+                return Some(Value::Address {
+                    addr: metatable.clone(),
+                });
+            }
+        }
+
+        None
+    }
+
+    /// the field `name` of the metatable of a value, nil if it has no metatable,
+    /// the metatable is read without metamethods
+    pub fn metafield(&self, value: &Value, name: &str) -> Value {
+        if let Some(Value::Address { addr }) = self.get_metatable(value) {
+            if let HeapObj::Table { table, metatable: _ } = &*self.dereference(&addr).unwrap().borrow() {
+                return table.index(&Value::Str {
+                    value: name.as_bytes().to_vec(),
+                });
+            }
+        }
+
+        Value::Nil
+    }
+
+    /// the line of the function `level` levels up the call stack, as error() counts them:
+    /// level 1 is the running function, at `line`, level 2 the one that called it, and so on
+    ///
+    /// returns `None` for a level out of the stack
+    pub fn level_line(&self, level: i64, line: usize) -> Option<usize> {
+        match level {
+            1 => Some(line),
+            level if level > 1 => {
+                let n = self.call_stack.len();
+                n.checked_sub(level as usize - 1).map(|i| self.call_stack[i].line)
+            }
+            _ => None,
+        }
+    }
+
+    /// the error object of a raised error as lua code gets it,
+    /// string messages raised with a position start with it, as in "script.lua:3: message"
+    ///
+    /// the position is in `chunkname`, or in the running chunk if it is None
+    pub fn error_value(&self, chunkname: Option<&str>, line: Option<usize>, value: Value) -> Value {
+        let chunkname = chunkname.unwrap_or(&self.chunkname);
+        match (line, value) {
+            (Some(line), Value::Str { value }) => Value::Str {
+                value: [format!("{}:{}: ", chunkname, line).into_bytes(), value].concat(),
+            },
+            (_, value) => value,
+        }
+    }
+
     /// alloc space for a function or table object
-    fn alloc(&mut self, obj: HeapObj) -> Address {
+    pub fn alloc(&mut self, obj: HeapObj) -> Address {
         let old_addr = self.cur_addr;
         self.cur_addr += 128; // 128 is just for fun, cause it's not the real memory layout :)
-        self.addr_space.insert(Address::new(old_addr), obj);
+        self.addr_space
+            .insert(Address::new(old_addr), Rc::new(RefCell::new(obj)));
 
         Address::new(old_addr)
     }
 
-    /// given address, return the function or table
-    fn dereference(&mut self, addr: &Address) -> Option<HeapObj> {
-        match self.addr_space.get(addr) {
-            Some(v) => Some(v.clone()),
-            None => None,
+    /// the type of a value, telling tables from functions
+    pub fn type_of(&self, value: &Value) -> String {
+        match value {
+            Value::Address { addr } => self.dereference(addr).unwrap().borrow().ty(),
+            _ => value.ty(),
         }
     }
 
+    /// name the variable or field an expression refers to, for error messages,
+    /// e.g. " (global 'x')"; empty if it cannot be told
+    fn describe(&self, exp: &Exp) -> String {
+        match exp {
+            Exp::Var {
+                var: Var::Name { name },
+            } => {
+                // a closure holds copies of the outer environments, the global one too,
+                // so the variable the name finds is global if it is the bottom one's
+                let global = match self.env_stack.iter().rev().find(|env| env.contain(name)) {
+                    Some(env) => env.shares(&self.env_stack[0], name),
+                    None => true,
+                };
+                if global {
+                    format!(" (global '{}')", name)
+                } else {
+                    String::new()
+                }
+            }
+            Exp::Var {
+                var: Var::TableIndex { exp, .. },
+            } => match exp.as_ref() {
+                Exp::Literal {
+                    value:
+                        Token {
+                            tok_type: TokenType::STRING { value },
+                            ..
+                        },
+                } => format!(" (field '{}')", String::from_utf8_lossy(value)),
+                _ => String::new(),
+            },
+            _ => String::new(),
+        }
+    }
+
+    /// given address, return the function or table
+    ///
+    /// the object is shared, not copied: read it with `borrow()` and update it with `borrow_mut()`
+    pub fn dereference(&self, addr: &Address) -> Option<Rc<RefCell<HeapObj>>> {
+        self.addr_space.get(addr).cloned()
+    }
+
     /// assgin a table field
+    ///
+    /// a key absent from the table is assigned through the `__newindex` field of its metatable:
+    /// a table is assigned in turn, a function is called with the table, the key and the value
     fn assign_table(
         &mut self,
         addr: &Address,
@@ -86,20 +233,72 @@ impl Interpreter {
         val: Value,
         line: usize,
     ) -> Result<(), RuntimeException> {
-        match self.addr_space.get_mut(addr) {
-            Some(v) => {
-                if let HeapObj::Table { table } = v {
+        check_key(&key, line)?;
+        let mut addr = addr.clone();
+        // bounds a chain of __newindex tables, which could loop forever
+        for _ in 0..MAX_INDEX_CHAIN {
+            let obj = self.dereference(&addr).unwrap();
+            if let HeapObj::Table { table, metatable: _ } = &mut *obj.borrow_mut() {
+                // existing keys are written directly
+                if !matches!(table.index(&key), Value::Nil) {
                     table.insert(key, val);
-                    Ok(())
-                } else {
-                    Err(RuntimeException::new_error(
+                    return Ok(());
+                }
+            } else {
+                return Err(RuntimeException::new_error(
+                    line,
+                    format!("attempt to assign a {} value", obj.borrow().ty()),
+                ));
+            }
+
+            let table_addr = Value::Address { addr: addr.clone() };
+            let handler = self.metafield(&table_addr, "__newindex");
+            match self.type_of(&handler).as_str() {
+                "nil" => {
+                    if let HeapObj::Table { table, metatable: _ } = &mut *obj.borrow_mut() {
+                        table.insert(key, val);
+                    }
+                    return Ok(());
+                }
+                "function" => {
+                    self.call_function(&handler, vec![table_addr, key, val], line)?;
+                    return Ok(());
+                }
+                "table" => {
+                    if let Value::Address { addr: next } = handler {
+                        addr = next;
+                    }
+                }
+                ty => {
+                    return Err(RuntimeException::new_error(
                         line,
-                        format!("attempt to assign a {} value", v.ty()),
+                        format!("attempt to index a {} value", ty),
                     ))
                 }
             }
-            None => unimplemented!(),
         }
+
+        Err(RuntimeException::new_error(
+            line,
+            String::from("'__newindex' chain too long; possible loop"),
+        ))
+    }
+
+    /// evaluate a list of expressions
+    ///
+    /// every expression is truncated to a single value except the last one,
+    /// which is expanded if it results in a value list (function call or '...')
+    fn eval_explist(&mut self, explist: &ExpList, line: usize) -> Result<Vec<Value>, RuntimeException> {
+        let mut values = Vec::new();
+        for (i, arg) in explist.0.iter().enumerate() {
+            if i + 1 < explist.0.len() {
+                values.push(self.eval(arg, line)?.compress());
+            } else {
+                values.append(&mut self.eval(arg, line)?.expand())
+            }
+        }
+
+        Ok(values)
     }
 
     /// assign a list of names, store them in the top most environment
@@ -109,32 +308,180 @@ impl Interpreter {
         explist: &ExpList,
         line: usize,
     ) -> Result<(), RuntimeException> {
-        let mut values = Vec::new();
-        // expand the last value if it results from a functioncall
-        for (i, arg) in explist.0.iter().enumerate() {
-            if i + 1 < explist.0.len() {
-                values.push(self.eval(arg, line)?.compress());
-            } else {
-                values.append(&mut self.eval(arg, line)?.expand())
+        let values = self.eval_explist(explist, line)?;
+        self.define_local_namelist(namelist, values);
+
+        Ok(())
+    }
+
+    /// bind already evaluated values to a list of names in the top most environment
+    ///
+    /// if the last name is '...' (a variadic parameter list),
+    /// the values left over are collected into it as a value list
+    fn define_local_namelist(&mut self, namelist: &NameList, mut values: Vec<Value>) {
+        let mut names = &namelist.0[..];
+        if let Some((last, rest)) = names.split_last() {
+            if last == "..." {
+                let varargs = values.split_off(rest.len().min(values.len()));
+                self.define_local(last, Value::ValueList { values: varargs });
+                names = rest;
             }
         }
-        // define the parameters
-        for i in 0..namelist.0.len() {
+
+        for (i, name) in names.iter().enumerate() {
             let value = values.get(i).unwrap_or(&Value::Nil);
-            self.define_local(&namelist.0[i], value.clone())
+            self.define_local(name, value.clone())
         }
-
-        Ok(())
     }
 }
 
 impl Interpreter {
     // input: an ast node
-    pub fn new() -> Self {
-        Self {
+    pub fn new(chunkname: &str) -> Self {
+        let mut interpreter = Self {
             env_stack: vec![Environment::global_env()],
             addr_space: HashMap::new(),
             cur_addr: 0x0000ffff0000, // a value just for fun
+            string_lib: Value::Nil,
+            package: Value::Nil,
+            chunkname: Rc::from(chunkname),
+            call_stack: Vec::new(),
+            print_handler: None,
+            output: Box::new(io::stdout()),
+            input: None,
+        };
+
+        // the libraries are tables, so they live on the heap,
+        // and count as loaded modules for require()
+        let mut loaded = Table::new();
+        for (name, table) in [
+            ("math", builtin::math()),
+            ("string", builtin::string()),
+            ("table", builtin::table()),
+            ("io", builtin::io()),
+            ("os", builtin::os()),
+        ] {
+            let addr = interpreter.alloc(HeapObj::Table {
+                table,
+                metatable: None,
+            });
+            interpreter.define_global(&name.to_string(), Value::Address { addr: addr.clone() });
+            loaded.insert(
+                Value::Str {
+                    value: name.as_bytes().to_vec(),
+                },
+                Value::Address { addr },
+            );
+        }
+        // kept aside, so reassigning the global 'string' does not change string methods
+        interpreter.string_lib = interpreter.get(&String::from("string")).unwrap().clone();
+
+        let addr = interpreter.alloc(HeapObj::Table {
+            table: loaded,
+            metatable: None,
+        });
+        let addr = interpreter.alloc(HeapObj::Table {
+            table: builtin::package(Value::Address { addr }),
+            metatable: None,
+        });
+        interpreter.package = Value::Address { addr };
+        interpreter.define_global(&String::from("package"), interpreter.package.clone());
+
+        interpreter
+    }
+
+    /// let `handler` receive the arguments of every print() call instead of writing them to stdout,
+    /// e.g. to route the output to a log or a console
+    pub fn set_print_handler(&mut self, handler: Box<PrintHandler>) {
+        self.print_handler = Some(handler);
+    }
+
+    /// make `func` the global function `name`, e.g. to let scripts call into the host.
+    /// it may be a closure keeping the host's state, as a counter of the calls
+    pub fn register_global(
+        &mut self,
+        name: &str,
+        func: impl FnMut(&mut Interpreter, Vec<Value>, usize) -> Result<Value, RuntimeException> + 'static,
+    ) {
+        let addr = self.alloc(HeapObj::HostFunction {
+            name: name.to_string(),
+            func: Rc::new(RefCell::new(func)),
+        });
+        self.env_stack[0].assign(name, Value::Address { addr });
+    }
+
+    /// the package library, which require() uses
+    pub fn package(&self) -> Value {
+        self.package.clone()
+    }
+
+    /// make a parsed chunk a function, which runs it among the globals when called.
+    /// errors raised in it are reported in `chunkname`
+    pub fn load(&mut self, block: Block, chunkname: &str) -> Value {
+        let func = HeapObj::Function {
+            // a chunk is variadic, '...' holds the arguments it is called with
+            parameters: Rc::new(NameList(vec![String::from("...")])),
+            body: Rc::new(block),
+            closure: Rc::from([]),
+            // the chunk is not defined at any line of it
+            def_line: 0,
+            chunkname: Rc::from(chunkname),
+        };
+        Value::Address {
+            addr: self.alloc(func),
+        }
+    }
+
+    /// drop what a failed run left on the stack, keeping the globals,
+    /// so the interpreter can go on with another chunk
+    pub fn reset_stack(&mut self) {
+        self.env_stack.truncate(1);
+        self.call_stack.clear();
+    }
+
+    /// let print() write to `output` instead of stdout, e.g. to a buffer shared with the host
+    pub fn set_output(&mut self, output: Box<dyn Write>) {
+        self.output = output;
+    }
+
+    /// the sink set by set_output, stdout by default
+    pub fn output(&mut self) -> &mut dyn Write {
+        self.output.as_mut()
+    }
+
+    /// let io.read() read from `input` instead of stdin
+    pub fn set_input(&mut self, input: Box<dyn BufRead>) {
+        self.input = Some(input);
+    }
+
+    /// call `f` with the source set by set_input, or stdin by default
+    pub fn with_input<T>(&mut self, f: impl FnOnce(&mut dyn BufRead) -> T) -> T {
+        match &mut self.input {
+            Some(input) => f(input.as_mut()),
+            // stdin is only locked while reading, the interactive mode reads it too
+            None => f(&mut io::stdin().lock()),
+        }
+    }
+
+    /// the handler set by set_print_handler, if any
+    pub fn print_handler(&mut self) -> Option<&mut PrintHandler> {
+        self.print_handler.as_deref_mut()
+    }
+
+    /// run a whole chunk, returning the values of its top-level return statement, if any
+    pub fn exec_chunk(&mut self, block: &Block) -> Result<Vec<Value>, RuntimeException> {
+        // the locals of the chunk are in its own scope, not among the globals
+        self.push_env(Environment::new(), 1)?;
+        let res = self.exec_block(block);
+        self.env_stack.truncate(1);
+        match res {
+            Ok(()) => Ok(Vec::new()),
+            Err(RuntimeException::RetResult { values }) => Ok(values),
+            Err(RuntimeException::Break { line }) => Err(RuntimeException::new_error(
+                line,
+                format!("<break> at line {} not inside a loop", line),
+            )),
+            Err(e) => Err(e),
         }
     }
 
@@ -168,24 +515,34 @@ impl Interpreter {
                 name,
                 parlist,
                 body,
-                line: _,
-            } => self.exec_func_decl(local.clone(), name, parlist, body),
+                line,
+            } => self.exec_func_decl(*local, name, parlist, body, *line),
 
             Stmt::FunctionCall {
                 prefixexp,
                 arguments,
                 line,
             } => {
-                self.eval_func_call(&prefixexp, &arguments, *line)?;
+                self.eval_func_call(prefixexp, arguments, *line)?;
+                Ok(())
+            }
+
+            Stmt::MethodCall {
+                prefixexp,
+                name,
+                arguments,
+                line,
+            } => {
+                self.eval_method_call(prefixexp, name, arguments, *line)?;
                 Ok(())
             }
 
             Stmt::GenericFor {
                 namelist,
-                table,
+                explist,
                 body,
                 line,
-            } => self.exec_generic_for(namelist, table, body, *line),
+            } => self.exec_generic_for(namelist, explist, body, *line),
 
             Stmt::NumericFor {
                 name,
@@ -216,6 +573,12 @@ impl Interpreter {
                 line,
             } => self.exec_while(condition, body, *line),
 
+            Stmt::RepeatUntil {
+                body,
+                condition,
+                line,
+            } => self.exec_repeat(body, condition, *line),
+
             Stmt::RetStmt { explist, line } => self.exec_return(explist, *line),
         }
     }
@@ -227,39 +590,30 @@ impl Interpreter {
         line: usize,
     ) -> Result<(), RuntimeException> {
         // evaluate values on the right hand side
-        let mut values = Vec::new();
-        for (i, arg) in right.0.iter().enumerate() {
-            if i + 1 < right.0.len() {
-                values.push(self.eval(arg, line)?.compress());
-            } else {
-                values.append(&mut self.eval(arg, line)?.expand())
-            }
-        }
+        let values = self.eval_explist(right, line)?;
         
         // evaluate expressions on the left hand side
         // expression can only reside in TableIndex's prefixes and keys
         let mut pres_keys = vec![(Value::Nil, Value::Nil); left.vars.len()];
-        for i in 0..left.vars.len() {
-            if let Var::TableIndex { prefixexp, exp } = &left.vars[i] {
-                pres_keys[i].0 = self.eval(prefixexp, line)?.compress();
-                pres_keys[i].1 = self.eval(exp, line)?.compress();
+        for (var, pre_key) in left.vars.iter().zip(pres_keys.iter_mut()) {
+            if let Var::TableIndex { prefixexp, exp, .. } = var {
+                pre_key.0 = self.eval(prefixexp, line)?.compress();
+                pre_key.1 = self.eval(exp, line)?.compress();
             }
         }
 
-        // assign
-        for i in 0..left.vars.len() {
-            match &left.vars[i] {
-                Var::Name { name } => {
-                    self.define_global(name, values.get(i).unwrap_or(&Value::Nil).clone())
-                }
-                Var::TableIndex { prefixexp: _, exp: _ } => {
-                    let res = pres_keys[i].0.clone();
+        // assign, the targets without a value on the right get nil
+        for (i, (var, (res, key))) in left.vars.iter().zip(pres_keys).enumerate() {
+            let value = values.get(i).cloned().unwrap_or(Value::Nil);
+            match var {
+                Var::Name { name } => self.define_global(name, value),
+                // errors are at the '[' or '.' of the field
+                Var::TableIndex { line, .. } => {
                     if let Value::Address { addr } = res {
-                        let key = pres_keys[i].1.clone();
-                        self.assign_table(&addr, key, values[i].clone(), line)?;
+                        self.assign_table(&addr, key, value, *line)?;
                     } else {
                         return Err(RuntimeException::new_error(
-                            line,
+                            *line,
                             format!("attempt to assign a {} value", res.ty()),
                         ));
                     }
@@ -284,93 +638,100 @@ impl Interpreter {
         &mut self,
         local: bool,
         name: &Name,
-        parlist: &NameList,
-        body: &Block,
+        parlist: &Rc<NameList>,
+        body: &Rc<Block>,
+        line: usize,
     ) -> Result<(), RuntimeException> {
+        if local {
+            // `local function f` is `local f; f = function`, the name is declared
+            // before the closure is built, so that the body can call itself
+            self.define_local(name, Value::Nil);
+        }
+
         let func = HeapObj::Function {
-            parameters: parlist.clone(),
-            body: body.clone(),
-            closure: self.env_stack.clone(),
+            parameters: Rc::clone(parlist),
+            body: Rc::clone(body),
+            closure: Rc::from(&self.env_stack[1..]),
+            def_line: line,
+            chunkname: Rc::clone(&self.chunkname),
         };
 
-        let addr = self.alloc(func);
+        let value = Value::Address {
+            addr: self.alloc(func),
+        };
 
         if local {
-            self.define_local(name, Value::Address { addr })
+            // the closure shares the variable declared above
+            self.env_stack.last_mut().unwrap().assign(name, value);
         } else {
-            self.define_global(name, Value::Address { addr })
+            self.define_global(name, value)
         }
 
         Ok(())
     }
 
+    /// `for namelist in f, s, control do body end` calls `f(s, control)` before
+    /// each iteration, binding its results to namelist, and stops when the first one is nil,
+    /// which becomes the next control value otherwise
     fn exec_generic_for(
         &mut self,
         namelist: &NameList,
-        table: &Exp,
+        explist: &ExpList,
         body: &Block,
         line: usize,
     ) -> Result<(), RuntimeException> {
-        let res = self.eval(table, line)?.compress();
-        let res_ty = res.ty();
-        match res {
-            Value::Address { addr } => {
-                let table = self.dereference(&addr).unwrap();
-                if let HeapObj::Table { table } = table {
-                    for (k, v) in table {
-                        self.push_env(Environment::new(), line)?;
-
-                        let values = vec![k, v];
-                        // assign namelist with valuelist (k, v)
-                        for (i, name) in namelist.0.iter().enumerate() {
-                            self.define_local(&name, values.get(i).unwrap_or(&Value::Nil).clone());
-                        }
-
-                        // catches the Break Exception
-                        match self.exec_block(&body) {
-                            Ok(_) => {}
-                            Err(RuntimeException::Break { line: _ }) => {
-                                // pop the stack before break the rust loop.
-                                self.pop_env();
-                                break;
-                            }
-                            // error occured, throw it 
-                            e => e?,
-                        }
+        let mut values = self.eval_explist(explist, line)?.into_iter();
+        let func = values.next().unwrap_or(Value::Nil);
+        let state = values.next().unwrap_or(Value::Nil);
+        let mut control = values.next().unwrap_or(Value::Nil);
+
+        loop {
+            let values = self
+                .call_function(&func, vec![state.clone(), control.clone()], line)?
+                .expand();
+            control = values.first().cloned().unwrap_or(Value::Nil);
+            if let Value::Nil = control {
+                break;
+            }
 
-                        self.pop_env();
-                    }
+            self.push_env(Environment::new(), line)?;
+            self.define_local_namelist(namelist, values);
 
-                    Ok(())
-                } else {
-                    Err(RuntimeException::new_error(
-                        line,
-                        format!("bad argument to 'pairs' (table expected, got function)"),
-                    ))
-                }
-            },
-            _ => Err(RuntimeException::new_error(
-                line,
-                format!("bad argument to 'pairs' (table expected, got {})", res_ty),
-            )),
+            // catches the Break Exception
+            let res = self.exec_block(body);
+            self.pop_env();
+            match res {
+                Ok(_) => {}
+                Err(RuntimeException::Break { line: _ }) => break,
+                e => e?,
+            }
         }
+
+        Ok(())
     }
 
-    /// just desugars the for statement into a while statement
-    /// by addding a surrounding block and some additional statements
+    /// the loop is driven by a hidden counter, the loop variable is a copy of it
     ///
     /// this is equivalent to
-    /// ```
+    /// ```lua
     /// do
-    ///     local name = start
-    ///     while name <= end do
+    ///     local count = 0
+    ///     while start + count * step <= end do      -- '>=' if step is negative
     ///         do
+    ///             local name = start + count * step
     ///             body
     ///         end
-    ///         name = name + step
+    ///         count = count + 1
     ///     end
     /// end
     /// ```
+    ///
+    /// where count is not visible to the body, so assigning to name
+    /// does not change the iterations
+    ///
+    /// the loop variable is an integer if both start and step are integers, otherwise a float.
+    /// an integer loop counts in integers, with the number of iterations computed before
+    /// it starts, so it neither loses precision nor overflows near the ends of the integers
     fn exec_numeric_for(
         &mut self,
         name: &Name,
@@ -380,52 +741,95 @@ impl Interpreter {
         body: &Block,
         line: usize,
     ) -> Result<(), RuntimeException> {
-        // defines the loop variable
-        self.push_env(Environment::new(), line)?;
-        let start_val = self.eval(start, line)?.compress();
-        self.define_local(name, start_val);
-
-        // generate condition expression and update statement
-        let var = Var::Name { name: name.clone() };
-        let condition = Exp::Binary {
-            left: Box::new(Exp::Var { var: var.clone() }),
-            operator: Token::new(0, TokenType::LESSEQUAL),
-            right: Box::new(end.clone()),
-        };
-        let update = Stmt::Assign {
-            left: VarList {
-                vars: vec![var.clone()],
-            },
-            right: ExpList(vec![Exp::Binary {
-                left: Box::new(Exp::Var { var: var.clone() }),
-                operator: Token::new(0, TokenType::PLUS),
-                right: Box::new(step.clone()),
-            }]),
-            line,
-        };
+        // the three expressions are evaluated once, before the loop starts
+        let start = self.for_number(start, "initial value", line)?;
+        let end = self.for_number(end, "limit", line)?;
+        let step = self.for_number(step, "step", line)?;
+        if step.number().unwrap() == 0.0 {
+            return Err(RuntimeException::new_error(
+                line,
+                String::from("'for' step is zero"),
+            ));
+        }
 
-        let new_body = Block {
-            statements: vec![
-                Stmt::DoBlockEnd {
-                    block: body.clone(),
-                    line,
-                },
-                update,
-            ],
-        };
+        if let (Value::Int { value: start }, Value::Int { value: step }) = (&start, &step) {
+            let (start, step) = (*start, *step);
+            let count = match for_count(start, &end, step) {
+                Some(count) => count,
+                None => return Ok(()),
+            };
+            let mut i = start;
+            for n in 0..=count {
+                if !self.exec_for_body(name, Value::Int { value: i }, body, line)? {
+                    break;
+                }
+                // the last value is not stepped past, where it could overflow
+                if n < count {
+                    i = i.wrapping_add(step);
+                }
+            }
+            return Ok(());
+        }
 
-        // generate the while statement
-        let whilestmt = Stmt::WhileStmt {
-            condition,
-            body: new_body,
-            line,
-        };
+        let (start, end, step) = (
+            start.number().unwrap().into_inner(),
+            end.number().unwrap().into_inner(),
+            step.number().unwrap().into_inner(),
+        );
+        let mut count = 0.0;
+        loop {
+            // computed from the count instead of adding up steps, so floats do not drift
+            let i = start + count * step;
+            // the direction of the loop depends on the sign of step
+            let in_range = if step > 0.0 { i <= end } else { i >= end };
+            if !in_range {
+                break;
+            }
+
+            let value = Value::Num {
+                value: OrderedFloat::from(i),
+            };
+            if !self.exec_for_body(name, value, body, line)? {
+                break;
+            }
+            count += 1.0;
+        }
+
+        Ok(())
+    }
 
-        self.exec(&whilestmt)?;
+    /// run one iteration of a numeric for loop, returns false if the body breaks out of it
+    fn exec_for_body(
+        &mut self,
+        name: &Name,
+        value: Value,
+        body: &Block,
+        line: usize,
+    ) -> Result<bool, RuntimeException> {
+        // every iteration gets a fresh loop variable
+        self.push_env(Environment::new(), line)?;
+        self.define_local(name, value);
 
+        // catches the Break Exception
+        let res = self.exec_block(body);
         self.pop_env();
+        match res {
+            Ok(_) => Ok(true),
+            Err(RuntimeException::Break { line: _ }) => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
 
-        Ok(())
+    /// evaluate the initial value, limit or step of a numeric for loop
+    fn for_number(&mut self, exp: &Exp, what: &str, line: usize) -> Result<Value, RuntimeException> {
+        let value = self.eval(exp, line)?.compress();
+        match value.to_number() {
+            Some(num) => Ok(num),
+            None => Err(RuntimeException::new_error(
+                line,
+                format!("'for' {} must be a number, got {}", what, self.type_of(&value)),
+            )),
+        }
     }
 
     fn exec_if(
@@ -436,7 +840,7 @@ impl Interpreter {
         option_else_branch: &Option<Block>,
         line: usize,
     ) -> Result<(), RuntimeException> {
-        let cond = self.eval(&condition, line)?.compress();
+        let cond = self.eval(condition, line)?.compress();
         if cond.truthy() {
             self.push_env(Environment::new(), line)?;
             self.exec_block(then_branch)?;
@@ -445,7 +849,7 @@ impl Interpreter {
             // flag records if any of the 'elseif' branch has been executed
             let mut flag = false;
             for (exp, block) in elseif_branches {
-                let cond = self.eval(&exp, line)?.compress();
+                let cond = self.eval(exp, line)?.compress();
                 if cond.truthy() {
                     self.push_env(Environment::new(), line)?;
                     self.exec_block(block)?;
@@ -475,13 +879,13 @@ impl Interpreter {
         body: &Block,
         line: usize,
     ) -> Result<(), RuntimeException> {
-        let mut cond = self.eval(&condition, line)?.compress();
+        let mut cond = self.eval(condition, line)?.compress();
         while cond.truthy() {
             self.push_env(Environment::new(), line)?;
-            match self.exec_block(&body) {
+            match self.exec_block(body) {
                 Ok(_) => {
                     // re-eval the condition 
-                    cond = self.eval(&condition, line)?.compress();
+                    cond = self.eval(condition, line)?.compress();
                     self.pop_env();
                 }
                 // catches the break statement 
@@ -496,13 +900,41 @@ impl Interpreter {
         Ok(())
     }
 
+    /// the body is executed at least once,
+    /// and the condition is evaluated inside the body's scope,
+    /// so it can see the local variables declared in the body
+    fn exec_repeat(
+        &mut self,
+        body: &Block,
+        condition: &Exp,
+        line: usize,
+    ) -> Result<(), RuntimeException> {
+        loop {
+            self.push_env(Environment::new(), line)?;
+            match self.exec_block(body) {
+                Ok(_) => {
+                    let cond = self.eval(condition, line)?.compress();
+                    self.pop_env();
+                    if cond.truthy() {
+                        break;
+                    }
+                }
+                // catches the break statement
+                Err(RuntimeException::Break { line: _ }) => {
+                    self.pop_env();
+                    break;
+                }
+                e => e?,
+            }
+        }
+
+        Ok(())
+    }
+
     /// evaluate the expressions and throws RuntimeException::RetResult
     fn exec_return(&mut self, explist: &ExpList, line: usize) -> Result<(), RuntimeException> {
-        let mut values = Vec::new();
-
-        for exp in explist.0.iter() {
-            values.push(self.eval(exp, line)?.compress());
-        }
+        // a trailing function call or '...' returns all of its values
+        let values = self.eval_explist(explist, line)?;
 
         Err(RuntimeException::RetResult { values })
     }
@@ -517,14 +949,24 @@ impl Interpreter {
                 operator,
                 right,
             } => self.eval_binary(operator, left, right, line),
+            // a call reports errors at its own line
             Exp::FunctionCall {
                 prefixexp,
                 arguments,
-            } => self.eval_func_call(prefixexp, arguments, line),
+                line,
+            } => self.eval_func_call(prefixexp, arguments, *line),
+            Exp::MethodCall {
+                prefixexp,
+                name,
+                arguments,
+                line,
+            } => self.eval_method_call(prefixexp, name, arguments, *line),
             Exp::Var { var } => self.eval_var(var, line),
-            Exp::Function { funcbody } => self.eval_func_exp(funcbody),
+            Exp::Function { funcbody, line } => self.eval_func_exp(funcbody, *line),
             Exp::TableConstructor { fieldlist } => self.eval_table(fieldlist, line),
-            Exp::Grouping { exp } => self.eval(exp, line),
+            // parentheses truncate a call or a vararg to its first value
+            Exp::Grouping { exp } => Ok(self.eval(exp, line)?.compress()),
+            Exp::Vararg => self.eval_vararg(),
         }
     }
 
@@ -536,6 +978,7 @@ impl Interpreter {
             TokenType::STRING { value } => Ok(Value::Str {
                 value: value.clone(),
             }),
+            TokenType::INTEGER { value } => Ok(Value::Int { value: *value }),
             TokenType::NUMBER { value } => Ok(Value::Num {
                 value: OrderedFloat::from(*value),
             }),
@@ -551,51 +994,69 @@ impl Interpreter {
     ) -> Result<Value, RuntimeException> {
         // first evaluate the right operand
         let right = self.eval(right, line)?.compress();
+        // errors of the operation itself, not of its operand, are reported at the operator
+        self.unary_operation(op, right).map_err(|e| e.at_col(op.col))
+    }
+
+    /// apply the unary operator to the value of its operand
+    fn unary_operation(&mut self, op: &Token, right: Value) -> Result<Value, RuntimeException> {
         match op.tok_type {
             TokenType::NOT => {
                 // all values except for 'nil' and 'false' are considered true
                 Ok(Value::Bool { b: !right.truthy() })
             }
             TokenType::MINUS => {
-                if let Value::Num { value } = right {
-                    Ok(Value::Num { value: -value })
-                } else {
-                    // if value can be converted to numbers, this will be valid
-                    if let Some(val) = right.number() {
-                        Ok(Value::Num { value: -val })
-                    } else {
-                        Err(RuntimeException::new_error(
-                            op.line,
-                            format!("attempt to perform negate operation on a '{}'", right.ty()),
-                        ))
+                // if value can be converted to numbers, this will be valid
+                match right.to_number() {
+                    Some(Value::Int { value }) => Ok(Value::Int {
+                        value: value.wrapping_neg(),
+                    }),
+                    Some(value) => Ok(Value::Num {
+                        value: -value.number().unwrap(),
+                    }),
+                    None => {
+                        // like the binary metamethods, __unm gets the operand twice
+                        let message = format!("attempt to perform negate operation on a '{}'", self.type_of(&right));
+                        self.binary_metamethod("__unm", right.clone(), right, op.line, message)
                     }
                 }
             }
             // get length operator
             TokenType::POUND => {
+                let handler = self.metafield(&right, "__len");
+                if !matches!(handler, Value::Nil) {
+                    // only the first result of the metamethod is the length
+                    return Ok(self.call_function(&handler, vec![right], op.line)?.compress());
+                }
+
                 let r_ty = right.ty();
                 if let Value::Address { addr } = right {
-                    if let Some(HeapObj::Table { table }) = self.dereference(&addr) {
-                        // returns the number of elements in the table
-                        Ok(Value::Num {
-                            value: OrderedFloat::from(table.len() as f64),
+                    if let HeapObj::Table { table, metatable: _ } = &*self.dereference(&addr).unwrap().borrow() {
+                        // the length of the sequence part, the same one table.insert appends after
+                        Ok(Value::Int {
+                            value: table.border() as i64,
                         })
                     } else {
                         Err(RuntimeException::new_error(
-                            line,
-                            format!("attempt to get length of a function value"),
+                            op.line,
+                            "attempt to get length of a function value".to_string(),
                         ))
                     }
                 } else if let Value::Str { value } = right {
                     // return the number of bytes in the string
-                    Ok(Value::Num { value: OrderedFloat::from(value.len() as f64) })
+                    Ok(Value::Int { value: byte_len(&value) })
                 } else {
                     Err(RuntimeException::new_error(
-                        line,
+                        op.line,
                         format!("attempt to get length of a {} value", r_ty),
                     ))
                 }
             }
+            // bitwise not
+            TokenType::TILDE => {
+                let value = self.bitwise_operand(&right, op.line)?;
+                Ok(Value::Int { value: !value })
+            }
             _ => unimplemented!(),
         }
     }
@@ -612,162 +1073,216 @@ impl Interpreter {
         right: &Exp,
         line: usize,
     ) -> Result<Value, RuntimeException> {
+        // keep the operand expressions to name them in error messages
+        let (left_exp, right_exp) = (left, right);
         // first evaluate the left expression
         let left = self.eval(left, line)?.compress();
 
+        // short circuit, the result is an operand itself rather than a boolean:
+        // 'and' gives the left one if it is false or nil, 'or' if it is neither,
+        // otherwise both give the right one
+        match op.tok_type {
+            TokenType::AND if !left.truthy() => return Ok(left),
+            TokenType::OR if left.truthy() => return Ok(left),
+            TokenType::AND | TokenType::OR => return Ok(self.eval(right, line)?.compress()),
+            _ => {}
+        }
+
+        let right = self.eval(right, line)?.compress();
+        // errors of the operation itself, not of its operands, are reported at the operator
+        self.binary_operation(op, left, right, left_exp, right_exp)
+            .map_err(|e| e.at_col(op.col))
+    }
+
+    /// apply the operator to the values of both operands, the expressions are for error messages
+    fn binary_operation(
+        &mut self,
+        op: &Token,
+        left: Value,
+        right: Value,
+        left_exp: &Exp,
+        right_exp: &Exp,
+    ) -> Result<Value, RuntimeException> {
         match op.tok_type {
             TokenType::PLUS => {
                 //  if the operand is a string and can be converted to num, then it will be valid
-                let right = self.eval(right, line)?.compress();
-                match (left.number(), right.number()) {
-                    (Some(a), Some(b)) => Ok(Value::Num { value: a + b }),
-                    _ => Err(RuntimeException::new_error(
+                match arith(&left, &right, i64::wrapping_add, |a, b| a + b) {
+                    Some(value) => Ok(value),
+                    None => self.binary_metamethod(
+                        "__add",
+                        left.clone(),
+                        right.clone(),
                         op.line,
-                        format!("attempt to add {} with {}", left.ty(), right.ty()),
-                    )),
+                        format!("attempt to add {} with {}", self.type_of(&left), self.type_of(&right)),
+                    ),
                 }
             }
 
             TokenType::MINUS => {
-                let right = self.eval(right, line)?.compress();
-                match (left.number(), right.number()) {
-                    (Some(a), Some(b)) => Ok(Value::Num { value: a - b }),
-                    _ => Err(RuntimeException::new_error(
+                match arith(&left, &right, i64::wrapping_sub, |a, b| a - b) {
+                    Some(value) => Ok(value),
+                    None => self.binary_metamethod(
+                        "__sub",
+                        left.clone(),
+                        right.clone(),
                         op.line,
-                        format!("attempt to subtract {} by {}", left.ty(), right.ty()),
-                    )),
+                        format!("attempt to subtract {} by {}", self.type_of(&left), self.type_of(&right)),
+                    ),
                 }
             }
 
             TokenType::MUL => {
-                let right = self.eval(right, line)?.compress();
-                match (left.number(), right.number()) {
-                    (Some(a), Some(b)) => Ok(Value::Num { value: a * b }),
-                    _ => Err(RuntimeException::new_error(
+                match arith(&left, &right, i64::wrapping_mul, |a, b| a * b) {
+                    Some(value) => Ok(value),
+                    None => self.binary_metamethod(
+                        "__mul",
+                        left.clone(),
+                        right.clone(),
                         op.line,
-                        format!("attempt to mul {} with {}", left.ty(), right.ty()),
-                    )),
+                        format!("attempt to mul {} with {}", self.type_of(&left), self.type_of(&right)),
+                    ),
                 }
             }
 
             TokenType::DIV => {
-                let right = self.eval(right, line)?.compress();
+                // always a float, even for integer operands
                 match (left.number(), right.number()) {
                     (Some(a), Some(b)) => Ok(Value::Num { value: a / b }),
-                    _ => Err(RuntimeException::new_error(
+                    _ => self.binary_metamethod(
+                        "__div",
+                        left.clone(),
+                        right.clone(),
                         op.line,
-                        format!("attempt to divide {} with {}", left.ty(), right.ty()),
-                    )),
+                        format!("attempt to divide {} with {}", self.type_of(&left), self.type_of(&right)),
+                    ),
                 }
             }
 
             TokenType::FLOORDIV => {
-                let right = self.eval(right, line)?.compress();
-                match (left.number(), right.number()) {
-                    (Some(a), Some(b)) => Ok(Value::Num {
-                        value: OrderedFloat::from((a / b).floor()),
-                    }),
-                    _ => Err(RuntimeException::new_error(
+                if integer_by_zero(&left, &right) {
+                    return Err(RuntimeException::new_error(
                         op.line,
-                        format!("attempt to divide {} with {}", left.ty(), right.ty()),
-                    )),
+                        String::from("attempt to perform 'n//0'"),
+                    ));
+                }
+                match arith(&left, &right, floor_div, |a, b| (a / b).floor()) {
+                    Some(value) => Ok(value),
+                    None => self.binary_metamethod(
+                        "__idiv",
+                        left.clone(),
+                        right.clone(),
+                        op.line,
+                        format!("attempt to divide {} with {}", self.type_of(&left), self.type_of(&right)),
+                    ),
                 }
             }
 
             TokenType::MOD => {
-                let right = self.eval(right, line)?.compress();
-                match (left.number(), right.number()) {
-                    (Some(a), Some(b)) => Ok(Value::Num {
-                        value: OrderedFloat::from(a % b),
-                    }),
-                    _ => Err(RuntimeException::new_error(
+                if integer_by_zero(&left, &right) {
+                    return Err(RuntimeException::new_error(
                         op.line,
-                        format!("attempt to divide {} with {}", left.ty(), right.ty()),
-                    )),
+                        String::from("attempt to perform 'n%0'"),
+                    ));
+                }
+                match arith(&left, &right, int_modulo, modulo) {
+                    Some(value) => Ok(value),
+                    None => self.binary_metamethod(
+                        "__mod",
+                        left.clone(),
+                        right.clone(),
+                        op.line,
+                        format!("attempt to divide {} with {}", self.type_of(&left), self.type_of(&right)),
+                    ),
                 }
             }
 
             TokenType::POW => {
-                let right = self.eval(right, line)?.compress();
-                match left.number() {
-                    Some(base) => {
-                        match right.number() {
-                            Some(power) => {
-                                Ok(Value::Num { value: OrderedFloat::from(base.powf(power.into_inner())) })
-                            },
-                            _ => Err(RuntimeException::new_error(
-                                    op.line,
-                                    format!("attempt to perform arithmetic on {} value", right.ty()),
-                                )),
-                        }
-                    },
-                    _ => Err(RuntimeException::new_error(
-                        op.line,
-                        format!("attempt to perform arithmetic on {} value", left.ty()),
-                    )),
+                match (left.number(), right.number()) {
+                    (Some(base), Some(power)) => Ok(Value::Num {
+                        value: OrderedFloat::from(base.powf(power.into_inner())),
+                    }),
+                    _ => {
+                        // name the first operand that is not a number
+                        let culprit = if left.number().is_none() { &left } else { &right };
+                        let message = format!("attempt to perform arithmetic on {} value", self.type_of(culprit));
+                        self.binary_metamethod("__pow", left.clone(), right.clone(), op.line, message)
+                    }
                 }
             }
 
             TokenType::DOTDOT => {
-                let right = self.eval(right, line)?.compress();
-                let (l_ty, r_ty) = (left.ty(), right.ty());
                 match (left.string(), right.string()) {
                     (Some(mut a), Some(b)) => {
-                        a.push_str(&b);
+                        a.extend(b);
                         Ok(Value::Str { value: a })
                     }
-                    _ => Err(RuntimeException::new_error(
-                        op.line,
-                        format!("attempt to concat {} with {}", l_ty, r_ty),
-                    )),
+                    // report the first operand that is not a string or a number
+                    (None, _) => {
+                        let message = format!(
+                            "attempt to concatenate a {} value{}",
+                            self.type_of(&left),
+                            self.describe(left_exp)
+                        );
+                        self.binary_metamethod("__concat", left, right, op.line, message)
+                    }
+                    (_, None) => {
+                        let message = format!(
+                            "attempt to concatenate a {} value{}",
+                            self.type_of(&right),
+                            self.describe(right_exp)
+                        );
+                        self.binary_metamethod("__concat", left, right, op.line, message)
+                    }
                 }
             }
 
+            TokenType::TILDE => {
+                let (a, b) = (
+                    self.bitwise_operand(&left, op.line)?,
+                    self.bitwise_operand(&right, op.line)?,
+                );
+                Ok(Value::Int { value: a ^ b })
+            }
+
+            TokenType::AMP | TokenType::PIPE | TokenType::SHL | TokenType::SHR => {
+                let (a, b) = (
+                    self.bitwise_operand(&left, op.line)?,
+                    self.bitwise_operand(&right, op.line)?,
+                );
+                let value = match op.tok_type {
+                    TokenType::AMP => a & b,
+                    TokenType::PIPE => a | b,
+                    TokenType::SHL => shift_left(a, b),
+                    _ => shift_left(a, b.wrapping_neg()),
+                };
+                Ok(Value::Int { value })
+            }
+
             TokenType::LESS => {
-                let right = self.eval(right, line)?.compress();
                 self.less(&left, &right, op.line)
             }
 
             TokenType::LESSEQUAL => {
-                let right = self.eval(right, line)?.compress();
                 self.less_equal(&left, &right, op.line)
             }
 
             TokenType::GREATER => {
-                let right = self.eval(right, line)?.compress();
                 self.greater(&left, &right, op.line)
             }
 
             TokenType::GREATEREQUAL => {
-                let right = self.eval(right, line)?.compress();
                 self.greater_equal(&left, &right, op.line)
             }
 
             TokenType::EQUALEQUAL => {
-                let right = self.eval(right, line)?.compress();
                 Ok(Value::Bool {
-                    b: self.equal(&left, &right),
+                    b: self.equal(&left, &right, op.line)?,
                 })
             }
 
             TokenType::NOTEQUAL => {
-                let right = self.eval(right, line)?.compress();
                 Ok(Value::Bool {
-                    b: !self.equal(&left, &right),
-                })
-            }
-
-            TokenType::AND => {
-                // short circuit
-                Ok(Value::Bool {
-                    b: left.truthy() && self.eval(right, line)?.compress().truthy(),
-                })
-            }
-
-            TokenType::OR => {
-                // short circuit
-                Ok(Value::Bool {
-                    b: left.truthy() || self.eval(right, line)?.compress().truthy(),
+                    b: !self.equal(&left, &right, op.line)?,
                 })
             }
 
@@ -775,47 +1290,120 @@ impl Interpreter {
         }
     }
 
+    /// calls the metamethod `event` of an arithmetic or concatenation operation whose operands
+    /// are not numbers (or strings), looking it up in the metatable of the left operand first,
+    /// then in the one of the right
+    ///
+    /// fails with `message` if neither operand has the metamethod
+    fn binary_metamethod(
+        &mut self,
+        event: &str,
+        left: Value,
+        right: Value,
+        line: usize,
+        message: String,
+    ) -> Result<Value, RuntimeException> {
+        let handler = match self.metafield(&left, event) {
+            Value::Nil => self.metafield(&right, event),
+            handler => handler,
+        };
+        if let Value::Nil = handler {
+            return Err(RuntimeException::new_error(line, message));
+        }
+
+        // only the first result of the metamethod is the value
+        Ok(self.call_function(&handler, vec![left, right], line)?.compress())
+    }
+
     /// defines the function and return its address
-    fn eval_func_exp(&mut self, funcbody: &FuncBody) -> Result<Value, RuntimeException> {
+    fn eval_func_exp(&mut self, funcbody: &FuncBody, line: usize) -> Result<Value, RuntimeException> {
         let func = HeapObj::Function {
-            parameters: funcbody.parlist.clone(),
-            body: funcbody.block.clone(),
-            closure: self.env_stack.clone(),
+            parameters: Rc::clone(&funcbody.parlist),
+            body: Rc::clone(&funcbody.block),
+            closure: Rc::from(&self.env_stack[1..]),
+            def_line: line,
+            chunkname: Rc::clone(&self.chunkname),
         };
         let addr = self.alloc(func);
         Ok(Value::Address { addr })
     }
 
+    /// '...' evaluates to the extra arguments of the enclosing variadic function
+    ///
+    /// the main chunk gets no arguments, so '...' is empty there
+    fn eval_vararg(&self) -> Result<Value, RuntimeException> {
+        match self.get(&String::from("...")) {
+            Some(values) => Ok(values),
+            None => Ok(Value::ValueList { values: vec![] }),
+        }
+    }
+
     /// evaluate variables(Name and TableIndex)
     fn eval_var(&mut self, var: &Var, line: usize) -> Result<Value, RuntimeException> {
         match var {
             Var::Name { name } => match self.get(name) {
-                Some(val) => Ok(val.clone()),
+                Some(val) => Ok(val),
                 None => Ok(Value::Nil),
             },
-            Var::TableIndex { prefixexp, exp } => {
-                let table_addr = self.eval(&prefixexp, line)?.compress();
-                if let Value::Address { addr } = table_addr {
-                    let table = self.dereference(&addr);
+            Var::TableIndex {
+                prefixexp,
+                exp,
+                line: index_line,
+            } => {
+                let table_addr = self.eval(prefixexp, line)?.compress();
+                let i = self.eval(exp, line)?.compress();
+                self.index(&table_addr, &i, *index_line)
+            }
+        }
+    }
 
-                    let i = self.eval(&exp, line)?.compress();
+    /// index the table `table_addr` with the key `i`
+    ///
+    /// strings are indexed through the string library, so `s:upper()` is `string.upper(s)`
+    ///
+    /// a key absent from a table is looked up through the `__index` field of its metatable:
+    /// a table is indexed in turn, a function is called with the table and the key
+    pub fn index(&mut self, table_addr: &Value, i: &Value, line: usize) -> Result<Value, RuntimeException> {
+        let mut table_addr = table_addr.clone();
+        // bounds a chain of __index tables, which could loop forever
+        for _ in 0..MAX_INDEX_CHAIN {
+            if let Value::Str { value: _ } = table_addr {
+                table_addr = self.string_lib.clone();
+                continue;
+            }
 
-                    if let Some(HeapObj::Table { table }) = table {
-                        Ok(table.index(&i))
-                    } else {
-                        Err(RuntimeException::new_error(
-                            line,
-                            format!("attempt to index a function value"),
-                        ))
+            if self.type_of(&table_addr) != "table" {
+                return Err(RuntimeException::new_error(
+                    line,
+                    format!("attempt to index a {} value", self.type_of(&table_addr)),
+                ));
+            }
+            if let Value::Address { addr } = &table_addr {
+                if let HeapObj::Table { table, metatable: _ } = &*self.dereference(addr).unwrap().borrow() {
+                    let value = table.index(i);
+                    if !matches!(value, Value::Nil) {
+                        return Ok(value);
                     }
-                } else {
-                    Err(RuntimeException::new_error(
-                        line,
-                        format!("attempt to index a {} value", table_addr.ty()),
-                    ))
                 }
             }
+
+            let handler = self.metafield(&table_addr, "__index");
+            match self.type_of(&handler).as_str() {
+                "nil" => return Ok(Value::Nil),
+                // only the first result of the function is the value
+                "function" => {
+                    return Ok(self
+                        .call_function(&handler, vec![table_addr, i.clone()], line)?
+                        .compress())
+                }
+                _ => table_addr = handler,
+            }
         }
+
+        Err(RuntimeException::new_error(
+            line,
+            String::from("'__index' chain too long; possible loop"),
+        ))
     }
 
     /// returns a Value::ValueList
@@ -826,60 +1414,203 @@ impl Interpreter {
         line: usize,
     ) -> Result<Value, RuntimeException> {
         let func_name = self.eval(prefixexp, line)?.compress();
-        if let Value::Address { addr } = func_name {
-            if let Some(HeapObj::Function {
-                parameters,
-                body,
-                mut closure,
-            }) = self.dereference(&addr)
-            {
-                let rec_n = self.env_stack.len();
+        // the arguments are evaluated in the caller's scope
+        let values = self.eval_explist(arguments, line)?;
+        if !self.callable(&func_name) {
+            let name = self.describe(prefixexp);
+            return Err(self.call_error(&func_name, &name, line));
+        }
+        self.call_named(&func_name, values, line, callee_name(prefixexp))
+    }
 
-                // push the environment when the closure was defined onto the stack
-                // in order to 'recall' those old on stack values
-                self.env_stack.append(&mut closure);
+    /// `prefixexp:name(arguments)` calls `prefixexp.name(prefixexp, arguments)`,
+    /// evaluating prefixexp only once
+    fn eval_method_call(
+        &mut self,
+        prefixexp: &Exp,
+        name: &Name,
+        arguments: &ExpList,
+        line: usize,
+    ) -> Result<Value, RuntimeException> {
+        let object = self.eval(prefixexp, line)?.compress();
+        let key = Value::Str {
+            value: name.as_bytes().to_vec(),
+        };
+        let func_name = self.index(&object, &key, line)?;
+        let mut values = vec![object];
+        values.append(&mut self.eval_explist(arguments, line)?);
+        if !self.callable(&func_name) {
+            return Err(self.call_error(&func_name, &format!(" (method '{}')", name), line));
+        }
+        self.call_named(&func_name, values, line, Some(format!("method '{}'", name)))
+    }
 
-                // the function body's own env
-                self.push_env(Environment::new(), line)?;
-                // define the local parameters
-                self.assign_local_namelist(&parameters, arguments, line)?;
+    /// whether a value can be called, a function or a table with a __call metamethod
+    fn callable(&self, value: &Value) -> bool {
+        match self.type_of(value).as_str() {
+            "function" => true,
+            "table" => !matches!(self.metafield(value, "__call"), Value::Nil),
+            _ => false,
+        }
+    }
 
-                let res = self.exec_block(&body);
+    /// the error of calling a value that is not a function, `name` tells what the call names it,
+    /// as in "attempt to call a nil value (global 'foo')"
+    fn call_error(&self, value: &Value, name: &str, line: usize) -> RuntimeException {
+        RuntimeException::new_error(
+            line,
+            format!("attempt to call a {} value{}", self.type_of(value), name),
+        )
+    }
 
-                // pop the body env
-                self.pop_env();
+    /// call the function with the evaluated arguments, returns a Value::ValueList
+    pub fn call_function(
+        &mut self,
+        func_name: &Value,
+        values: Vec<Value>,
+        line: usize,
+    ) -> Result<Value, RuntimeException> {
+        self.call_named(func_name, values, line, None)
+    }
 
-                // pop the closure
-                while self.env_stack.len() > rec_n {
-                    self.pop_env();
+    /// call_function for a call written in the source, `name` tells how it names the function,
+    /// like "function 'foo'", for the traceback
+    fn call_named(
+        &mut self,
+        func_name: &Value,
+        values: Vec<Value>,
+        line: usize,
+        name: Option<String>,
+    ) -> Result<Value, RuntimeException> {
+        // a table is callable through the __call field of its metatable,
+        // which gets the table before the arguments
+        if self.type_of(func_name) == "table" {
+            let handler = self.metafield(func_name, "__call");
+            if !matches!(handler, Value::Nil) {
+                let mut arguments = vec![func_name.clone()];
+                arguments.extend(values);
+                return self.call_named(&handler, arguments, line, name);
+            }
+        }
+
+        if let Value::Address { addr } = func_name {
+            let native = match &*self.dereference(addr).unwrap().borrow() {
+                HeapObj::NativeClosure { func, upvalues } => Some((*func, upvalues.clone())),
+                _ => None,
+            };
+            let host = match &*self.dereference(addr).unwrap().borrow() {
+                HeapObj::HostFunction { name, func } => Some((name.clone(), Rc::clone(func))),
+                _ => None,
+            };
+            if let Some((name, func)) = host {
+                // the closure is borrowed while it runs, so it cannot be called again from inside
+                let mut func = func.try_borrow_mut().map_err(|_| {
+                    RuntimeException::new_error(
+                        line,
+                        format!("host function '{}' called while it is running", name),
+                    )
+                })?;
+                return func(self, values, line);
+            }
+            if let Some((func, mut upvalues)) = native {
+                let res = func(self, &mut upvalues, values, line);
+                // store the updated upvalues, so the closure keeps its state between calls
+                if let HeapObj::NativeClosure { func: _, upvalues: old } =
+                    &mut *self.dereference(addr).unwrap().borrow_mut()
+                {
+                    *old = upvalues;
                 }
+                return res;
+            }
+
+            // share the parts of the function, so the object is not borrowed while its body runs
+            let function = match &*self.dereference(addr).unwrap().borrow() {
+                HeapObj::Function {
+                    parameters,
+                    body,
+                    closure,
+                    def_line,
+                    chunkname,
+                } => Some((
+                    Rc::clone(parameters),
+                    Rc::clone(body),
+                    Rc::clone(closure),
+                    *def_line,
+                    Rc::clone(chunkname),
+                )),
+                _ => None,
+            };
+            if let Some((parameters, body, closure, def_line, chunkname)) = function {
+                if self.call_stack.len() >= MAX_CALLS {
+                    return Err(RuntimeException::new_error(
+                        line,
+                        "exceeds the maximum stack sizes".to_string(),
+                    ));
+                }
+
+                // the body sees the globals and the scopes the function is defined in,
+                // not the ones of its caller, which are put aside until it returns
+                let mut caller_stack = std::mem::take(&mut self.env_stack);
+                let globals = std::mem::replace(&mut caller_stack[0], Environment::new());
+                self.env_stack.push(globals);
+                self.env_stack.extend(closure.iter().cloned());
+                let caller_chunkname = std::mem::replace(&mut self.chunkname, Rc::clone(&chunkname));
+
+                // the function body's own env
+                self.env_stack.push(Environment::new());
+                // define the local parameters
+                self.define_local_namelist(&parameters, values);
 
-                match res {
+                self.call_stack.push(CallFrame {
+                    name,
+                    line,
+                    def_line,
+                    chunkname,
+                });
+                let mut res = match self.exec_block(&body) {
                     // catches the returned values
-                    Err(RuntimeException::RetResult { values }) => {
-                        return Ok(Value::ValueList { values })
-                    }
+                    Err(RuntimeException::RetResult { values }) => Ok(Value::ValueList { values }),
+                    // a break never leaves the function it is in,
+                    // it reaching here means there is no loop around it inside the function
+                    Err(RuntimeException::Break { line }) => Err(RuntimeException::new_error(
+                        line,
+                        format!("<break> at line {} not inside a loop", line),
+                    )),
+                    // no return statement, no values, as with an empty return
+                    Ok(()) => Ok(Value::ValueList { values: Vec::new() }),
                     // error occured when exec function's body
-                    e => e?,
+                    Err(e) => Err(e),
+                };
+                // the innermost function an error leaves has the whole stack it was raised in,
+                // and the chunk it was raised in
+                if let Err(RuntimeException::RuntimeError {
+                    traceback,
+                    chunkname,
+                    ..
+                }) = &mut res
+                {
+                    if traceback.is_empty() {
+                        *traceback = self.call_stack.clone();
+                        *chunkname = Some(Rc::clone(&self.chunkname));
+                    }
                 }
+                self.call_stack.pop();
+
+                // back to the scopes of the caller, with the globals the function may have set
+                caller_stack[0] = std::mem::replace(&mut self.env_stack[0], Environment::new());
+                self.env_stack = caller_stack;
+                self.chunkname = caller_chunkname;
 
-                // no return statement, no error occured, return nil as default
-                Ok(Value::Nil)
+                res
             } else {
-                Err(RuntimeException::new_error(
-                    line,
-                    format!("attempt to call a table value"),
-                ))
+                Err(self.call_error(func_name, "", line))
             }
-        } else if let Value::Print = func_name {
-            // call the default print function
-            self.call_print(arguments, line)
+        } else if let Value::NativeFunction { name: _, func } = func_name {
+            // call a builtin function
+            func(self, values, line)
         } else {
             // not a callable object
-            Err(RuntimeException::new_error(
-                line,
-                format!("attempt to call a {} value", func_name.ty()),
-            ))
+            Err(self.call_error(func_name, "", line))
         }
     }
 
@@ -893,7 +1624,7 @@ impl Interpreter {
         let mut table = Table::new();
         // field can be 'exp' or 'Name=exp'
         // num_index used to record the number of 'exp's
-        let mut num_index = 1.0;
+        let mut num_index = 1;
 
         // when the trailing field is a functioncall, expand its result
         for (i, field) in fieldlist.0.iter().enumerate() {
@@ -904,18 +1635,18 @@ impl Interpreter {
                 match &field.name {
                     Some(name) => table.insert(
                         Value::Str {
-                            value: name.clone(),
+                            value: name.as_bytes().to_vec(),
                         },
                         val,
                     ),
                     None => {
                         table.insert(
-                            Value::Num {
-                                value: OrderedFloat::from(num_index),
+                            Value::Int {
+                                value: num_index,
                             },
                             val,
                         );
-                        num_index += 1.0;
+                        num_index += 1;
                     }
                 }
             } else {
@@ -927,20 +1658,20 @@ impl Interpreter {
                      match &field.name {
                         Some(name) => table.insert(
                             Value::Str {
-                                value: name.clone(),
+                                value: name.as_bytes().to_vec(),
                             },
-                            values.get(0).unwrap_or(&Value::Nil).clone(),
+                            values.first().unwrap_or(&Value::Nil).clone(),
                         ),
                         None => {
                             // the last field is 'exp' and is a function call
                             for value in values {
                                 table.insert(
-                                    Value::Num {
-                                        value: OrderedFloat::from(num_index),
+                                    Value::Int {
+                                        value: num_index,
                                     },
                                     value
                                 );
-                                num_index += 1.0;
+                                num_index += 1;
                             }
                         }
                     }
@@ -949,25 +1680,28 @@ impl Interpreter {
                     match &field.name {
                         Some(name) => table.insert(
                             Value::Str {
-                                value: name.clone(),
+                                value: name.as_bytes().to_vec(),
                             },
                             val,
                         ),
                         None => {
                             table.insert(
-                                Value::Num {
-                                    value: OrderedFloat::from(num_index),
+                                Value::Int {
+                                    value: num_index,
                                 },
                                 val,
                             );
-                            num_index += 1.0;
+                            num_index += 1;
                         }
                     }
                 }
             }
         }
 
-        let addr = self.alloc(HeapObj::Table { table });
+        let addr = self.alloc(HeapObj::Table {
+            table,
+            metatable: None,
+        });
 
         Ok(Value::Address { addr })
     }
@@ -978,52 +1712,142 @@ impl Interpreter {
     ///
     /// functions and tables are considered equal only if they are the same object
     /// every time you create a new object, this new object is different from the prior ones
-    fn equal(&self, left: &Value, right: &Value) -> bool {
+    ///
+    /// two different tables are equal if the `__eq` metamethod of either says so
+    fn equal(&mut self, left: &Value, right: &Value, line: usize) -> Result<bool, RuntimeException> {
+        let raw = self.raw_equal(left, right);
+        if raw || self.type_of(left) != "table" || self.type_of(right) != "table" {
+            return Ok(raw);
+        }
+
+        Ok(self.compare_metamethod("__eq", left, right, line)?.unwrap_or(false))
+    }
+
+    /// primitive equality, tables are only equal to themselves
+    fn raw_equal(&self, left: &Value, right: &Value) -> bool {
         match (left, right) {
-            (Value::Num { value: a }, Value::Num { value: b }) => a == b,
+            (Value::Nil, Value::Nil) => true,
+            (Value::Bool { b: a }, Value::Bool { b }) => a == b,
+            (Value::Int { value: a }, Value::Int { value: b }) => a == b,
+            // compare the raw floats, nan is not equal to itself
+            (Value::Num { value: a }, Value::Num { value: b }) => a.into_inner() == b.into_inner(),
+            // an integer and a float are equal if they have the same mathematical value
+            (Value::Int { value: a }, Value::Num { value: b })
+            | (Value::Num { value: b }, Value::Int { value: a }) => {
+                float_to_integer(b.into_inner()) == Some(*a)
+            }
             (Value::Str { value: a }, Value::Str { value: b }) => a == b,
             (Value::Address { addr: a }, Value::Address { addr: b }) => a == b,
+            // each builtin is registered once under its own name
+            (
+                Value::NativeFunction { name: a, func: _ },
+                Value::NativeFunction { name: b, func: _ },
+            ) => a == b,
             _ => false,
         }
     }
 
+    /// convert an operand of a bitwise operator to an integer
+    ///
+    /// numbers (and strings convertible to numbers) must have an exact integer representation
+    fn bitwise_operand(&self, value: &Value, line: usize) -> Result<i64, RuntimeException> {
+        match (value.number(), value.integer()) {
+            (_, Some(i)) => Ok(i),
+            (Some(_), None) => Err(RuntimeException::new_error(
+                line,
+                String::from("number has no integer representation"),
+            )),
+            _ => Err(RuntimeException::new_error(
+                line,
+                format!("attempt to perform bitwise operation on a {} value", value.ty()),
+            )),
+        }
+    }
+
     /// if both are numbers or strings, compare the normal way (value and alphabetic order)
     ///
+    /// integers are compared as integers, other numbers as raw f64 rather than `OrderedFloat`,
+    /// so any comparison involving NaN is false, as in Lua.
+    /// strings are compared byte by byte. values of different types are never ordered.
+    ///
     /// comparison a > b is translated to b < a and a >= b translated to b <= a
-    fn less(&self, left: &Value, right: &Value, line: usize) -> Result<Value, RuntimeException> {
+    ///
+    /// other values are compared by their `__lt` metamethod
+    pub fn less(&mut self, left: &Value, right: &Value, line: usize) -> Result<Value, RuntimeException> {
         match (left, right) {
-            (Value::Num { value: a }, Value::Num { value: b }) => Ok(Value::Bool { b: a < b }),
+            (Value::Int { value: a }, Value::Int { value: b }) => Ok(Value::Bool { b: a < b }),
+            (Value::Int { value: _ } | Value::Num { value: _ }, Value::Int { value: _ } | Value::Num { value: _ }) => {
+                Ok(Value::Bool {
+                    b: left.number().unwrap().into_inner() < right.number().unwrap().into_inner(),
+                })
+            }
             (Value::Str { value: a }, Value::Str { value: b }) => Ok(Value::Bool { b: a < b }),
-            _ => Err(RuntimeException::new_error(
-                line,
-                format!("attempt to compare {} with {}", left.ty(), right.ty()),
-            )),
+            _ => match self.compare_metamethod("__lt", left, right, line)? {
+                Some(b) => Ok(Value::Bool { b }),
+                None => Err(RuntimeException::new_error(
+                    line,
+                    format!("attempt to compare {} with {}", self.type_of(left), self.type_of(right)),
+                )),
+            },
         }
     }
 
     /// if both are numbers or strings, compare the normal way (value and alphabetic order)
     ///
     /// comparison a > b is translated to b < a and a >= b translated to b <= a
+    ///
+    /// other values are compared by their `__le` metamethod
     fn less_equal(
-        &self,
+        &mut self,
         left: &Value,
         right: &Value,
         line: usize,
     ) -> Result<Value, RuntimeException> {
         match (left, right) {
-            (Value::Num { value: a }, Value::Num { value: b }) => Ok(Value::Bool { b: a <= b }),
+            (Value::Int { value: a }, Value::Int { value: b }) => Ok(Value::Bool { b: a <= b }),
+            (Value::Int { value: _ } | Value::Num { value: _ }, Value::Int { value: _ } | Value::Num { value: _ }) => {
+                Ok(Value::Bool {
+                    b: left.number().unwrap().into_inner() <= right.number().unwrap().into_inner(),
+                })
+            }
             (Value::Str { value: a }, Value::Str { value: b }) => Ok(Value::Bool { b: a <= b }),
-            _ => Err(RuntimeException::new_error(
-                line,
-                format!("attempt to compare {} with {}", left.ty(), right.ty()),
-            )),
+            _ => match self.compare_metamethod("__le", left, right, line)? {
+                Some(b) => Ok(Value::Bool { b }),
+                None => Err(RuntimeException::new_error(
+                    line,
+                    format!("attempt to compare {} with {}", self.type_of(left), self.type_of(right)),
+                )),
+            },
         }
     }
 
+    /// calls the comparison metamethod `event` of the left operand, or else of the right one,
+    /// and converts its result to a boolean
+    ///
+    /// returns `None` if neither operand has the metamethod
+    fn compare_metamethod(
+        &mut self,
+        event: &str,
+        left: &Value,
+        right: &Value,
+        line: usize,
+    ) -> Result<Option<bool>, RuntimeException> {
+        let handler = match self.metafield(left, event) {
+            Value::Nil => self.metafield(right, event),
+            handler => handler,
+        };
+        if let Value::Nil = handler {
+            return Ok(None);
+        }
+
+        let res = self.call_function(&handler, vec![left.clone(), right.clone()], line)?;
+        Ok(Some(res.compress().truthy()))
+    }
+
     /// if both are numbers or strings, compare the normal way (value and alphabetic order)
     ///
     /// comparison a > b is translated to b < a and a >= b translated to b <= a
-    fn greater(&self, left: &Value, right: &Value, line: usize) -> Result<Value, RuntimeException> {
+    fn greater(&mut self, left: &Value, right: &Value, line: usize) -> Result<Value, RuntimeException> {
         self.less(right, left, line)
     }
 
@@ -1031,31 +1855,178 @@ impl Interpreter {
     ///
     /// comparison a > b is translated to b < a and a >= b translated to b <= a
     fn greater_equal(
-        &self,
+        &mut self,
         left: &Value,
         right: &Value,
         line: usize,
     ) -> Result<Value, RuntimeException> {
         self.less_equal(right, left, line)
     }
+}
 
-    fn call_print(&mut self, arguments: &ExpList, line: usize) -> Result<Value, RuntimeException> {
-        let mut values = Vec::new();
-        for arg in arguments.0.iter() {
-            values.push(self.eval(&arg, line)?.compress());
+/// nil and NaN cannot be table keys, reading them gives nil but assigning them is an error
+pub fn check_key(key: &Value, line: usize) -> Result<(), RuntimeException> {
+    match key {
+        Value::Nil => Err(RuntimeException::new_error(line, String::from("table index is nil"))),
+        Value::Num { value } if value.is_nan() => {
+            Err(RuntimeException::new_error(line, String::from("table index is NaN")))
         }
+        _ => Ok(()),
+    }
+}
+
+/// applies an arithmetic operator to two operands convertible to numbers,
+/// two integers give an integer with `int`, otherwise the operands are converted
+/// to floats for `float`
+///
+/// returns `None` if an operand is not a number
+fn arith(
+    left: &Value,
+    right: &Value,
+    int: fn(i64, i64) -> i64,
+    float: fn(f64, f64) -> f64,
+) -> Option<Value> {
+    match (left.to_number()?, right.to_number()?) {
+        (Value::Int { value: a }, Value::Int { value: b }) => Some(Value::Int { value: int(a, b) }),
+        (a, b) => Some(Value::Num {
+            value: OrderedFloat::from(float(a.number()?.into_inner(), b.number()?.into_inner())),
+        }),
+    }
+}
 
-        for value in values {
-            print!("{}\t", value)
+/// is it an integer division (or modulo) by the integer zero, which has no result
+fn integer_by_zero(left: &Value, right: &Value) -> bool {
+    matches!(
+        (left.to_number(), right.to_number()),
+        (Some(Value::Int { value: _ }), Some(Value::Int { value: 0 }))
+    )
+}
+
+/// integer division rounding the quotient towards minus infinity, b is not zero
+fn floor_div(a: i64, b: i64) -> i64 {
+    let q = a.wrapping_div(b);
+    if a.wrapping_rem(b) != 0 && (a < 0) != (b < 0) {
+        q - 1
+    } else {
+        q
+    }
+}
+
+/// the integer version of `modulo`, b is not zero
+fn int_modulo(a: i64, b: i64) -> i64 {
+    let m = a.wrapping_rem(b);
+    if m != 0 && (m < 0) != (b < 0) {
+        m + b
+    } else {
+        m
+    }
+}
+
+/// shift the bits of `a` left by `n`, or right by `-n` when it is negative,
+/// filling in zeros; shifting by 64 or more bits gives 0
+fn shift_left(a: i64, n: i64) -> i64 {
+    if n <= -64 || n >= 64 {
+        0
+    } else if n >= 0 {
+        ((a as u64) << n) as i64
+    } else {
+        ((a as u64) >> -n) as i64
+    }
+}
+
+/// the remainder of a division that rounds the quotient towards minus infinity,
+/// `a - floor(a / b) * b`, so the result has the sign of `b`
+///
+/// computed from the truncated remainder, which stays exact and gives `a % inf == a`
+/// for a positive `a`; a zero divisor gives NaN
+fn modulo(a: f64, b: f64) -> f64 {
+    let m = a % b;
+    if m != 0.0 && (m < 0.0) != (b < 0.0) {
+        m + b
+    } else {
+        m
+    }
+}
+
+/// the number of steps an integer for loop takes after its first iteration,
+/// or None if it does not run at all, as lua computes it
+///
+/// a float limit is floored (ceiled if step is negative) to an integer,
+/// and clipped to the integers if it is beyond them
+fn for_count(start: i64, end: &Value, step: i64) -> Option<u64> {
+    let end = match end {
+        Value::Int { value } => *value,
+        _ => {
+            let end = end.number().unwrap().into_inner();
+            let end = if step > 0 { end.floor() } else { end.ceil() };
+            if end.is_nan() {
+                return None;
+            } else if end >= 9223372036854775808.0 {
+                // beyond the largest integer, which a loop going down cannot start at
+                if step < 0 {
+                    return None;
+                }
+                i64::MAX
+            } else if end < -9223372036854775808.0 {
+                if step > 0 {
+                    return None;
+                }
+                i64::MIN
+            } else {
+                end as i64
+            }
         }
-        print!("\n");
+    };
+    if (step > 0 && start > end) || (step < 0 && start < end) {
+        return None;
+    }
 
-        Ok(Value::Nil)
+    // the distance is counted in unsigned integers, where it cannot overflow
+    Some(if step > 0 {
+        (end as u64).wrapping_sub(start as u64) / step as u64
+    } else {
+        (start as u64).wrapping_sub(end as u64) / ((-(step + 1)) as u64 + 1)
+    })
+}
+
+/// how a call names the function it calls, for the traceback
+fn callee_name(prefixexp: &Exp) -> Option<String> {
+    match prefixexp {
+        Exp::Var {
+            var: Var::Name { name },
+        } => Some(format!("function '{}'", name)),
+        Exp::Var {
+            var: Var::TableIndex { exp, .. },
+        } => match &**exp {
+            Exp::Literal {
+                value:
+                    Token {
+                        tok_type: TokenType::STRING { value },
+                        ..
+                    },
+            } => Some(format!("field '{}'", String::from_utf8_lossy(value))),
+            _ => None,
+        },
+        _ => None,
     }
 }
 
 pub enum RuntimeException {
-    RuntimeError { line: usize, message: String },
+    // value is the error object, any value raised by error() or a message string,
+    // a string raised with a line is reported with the position, as in "script.lua:3: message"
+    //
+    // traceback is the stack of lua functions the error was raised in, empty until the error
+    // leaves the innermost of them
+    //
+    // col is where on the line the error was raised, if it is known, for the report,
+    // and chunkname the chunk it was raised in, None for the main one
+    RuntimeError {
+        line: Option<usize>,
+        col: Option<usize>,
+        chunkname: Option<Rc<str>>,
+        value: Value,
+        traceback: Vec<CallFrame>,
+    },
 
     RetResult { values: Vec<Value> },
     Break { line: usize },
@@ -1063,21 +2034,111 @@ pub enum RuntimeException {
 
 impl RuntimeException {
     pub fn new_error(line: usize, message: String) -> Self {
-        Self::RuntimeError { line, message }
+        Self::RuntimeError {
+            line: Some(line),
+            col: None,
+            chunkname: None,
+            value: Value::Str {
+                value: message.into_bytes(),
+            },
+            traceback: Vec::new(),
+        }
+    }
+
+    /// report an error raised right here, not in a function called from here, at column `at`
+    pub fn at_col(mut self, at: usize) -> Self {
+        if let Self::RuntimeError {
+            col: col @ None,
+            traceback,
+            ..
+        } = &mut self
+        {
+            if traceback.is_empty() {
+                *col = Some(at);
+            }
+        }
+        self
+    }
+}
+
+/// the most levels a traceback shows, the ones in the middle of a deeper stack are skipped
+const TRACEBACK_LEVELS: usize = 22;
+
+/// print the functions an uncaught error was raised in, the innermost first,
+/// each at the line it was running when the error was raised at `line`
+fn report_traceback(filename: &str, line: Option<usize>, traceback: &[CallFrame]) {
+    if traceback.is_empty() {
+        return;
+    }
+
+    // a function is running the call to the function after it, the innermost one the error
+    let mut current = vec![line];
+    current.extend(traceback.iter().rev().map(|frame| Some(frame.line)));
+    let mut levels: Vec<String> = traceback
+        .iter()
+        .rev()
+        .map(|frame| {
+            let name = match &frame.name {
+                Some(name) => name.clone(),
+                // a chunk given to load() is defined at line 0
+                None if frame.def_line == 0 => String::from("main chunk"),
+                None => format!("function <{}:{}>", frame.chunkname, frame.def_line),
+            };
+            (name, &*frame.chunkname)
+        })
+        .chain([(String::from("main chunk"), filename)])
+        .zip(current)
+        .map(|((name, chunkname), line)| match line {
+            Some(line) => format!("{}:{}: in {}", chunkname, line, name),
+            None => format!("{}: in {}", chunkname, name),
+        })
+        .collect();
+
+    if levels.len() > TRACEBACK_LEVELS {
+        let skipped = levels.len() - (TRACEBACK_LEVELS - 1);
+        levels.splice(
+            10..10 + skipped,
+            [format!("...\t(skipping {} levels)", skipped)],
+        );
+    }
+
+    eprintln!("stack traceback:");
+    for level in levels {
+        eprintln!("\t{}", level);
     }
 }
 
 impl RuaError for RuntimeException {
-    fn report(&self, filename: &str) {
+    fn message(&self, filename: &str) -> String {
         match self {
-            Self::RuntimeError { line, message } => {
-                eprintln!("rua: {}:{}: {}", filename, line, message)
+            Self::RuntimeError {
+                line: Some(line),
+                col,
+                chunkname,
+                value: Value::Str { value },
+                ..
+            } => {
+                let col = col.map_or(String::new(), |col| format!("{}:", col));
+                let chunkname = chunkname.as_deref().unwrap_or(filename);
+                format!("{}:{}:{} {}", chunkname, line, col, String::from_utf8_lossy(value))
             }
-            Self::RetResult { values: _ } => {}
-            Self::Break { line } => eprintln!(
-                "rua: {}:{}: <break> at line {} not inside a loop",
+            Self::RuntimeError { value, .. } => value.to_string(),
+            Self::RetResult { values: _ } => String::new(),
+            Self::Break { line } => format!(
+                "{}:{}: <break> at line {} not inside a loop",
                 filename, line, line
             ),
         }
     }
+
+    fn report(&self, filename: &str) {
+        match self {
+            Self::RuntimeError { line, traceback, .. } => {
+                eprintln!("rua: {}", self.message(filename));
+                report_traceback(filename, *line, traceback);
+            }
+            Self::RetResult { values: _ } => {}
+            Self::Break { line: _ } => eprintln!("rua: {}", self.message(filename)),
+        }
+    }
 }
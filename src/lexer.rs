@@ -6,18 +6,23 @@ use crate::{
 };
 
 pub struct Lexer<'a> {
-    source: &'a mut Vec<u8>,
+    source: &'a [u8],
     current: usize,
     line: usize,
+    // where the current line starts, and the column the current token starts at
+    line_start: usize,
+    col: usize,
     keywords: HashMap<&'static str, TokenType>,
 }
 
 impl<'a> Lexer<'a> {
-    pub fn new(source: &'a mut Vec<u8>) -> Self {
+    pub fn new(source: &'a [u8]) -> Self {
         Self {
             source,
             current: 0,
             line: 1,
+            line_start: 0,
+            col: 1,
             keywords: HashMap::from([
                 ("and", TokenType::AND),
                 ("or", TokenType::OR),
@@ -26,6 +31,8 @@ impl<'a> Lexer<'a> {
                 ("else", TokenType::ELSE),
                 ("elseif", TokenType::ELSEIF),
                 ("while", TokenType::WHILE),
+                ("repeat", TokenType::REPEAT),
+                ("until", TokenType::UNTIL),
                 ("for", TokenType::FOR),
                 ("do", TokenType::DO),
                 ("end", TokenType::END),
@@ -46,47 +53,61 @@ impl<'a> Lexer<'a> {
     pub fn lex(&mut self) -> Result<Vec<Token>, LexError> {
         let mut tokens: Vec<Token> = Vec::new();
 
+        // a first line starting with `#!`, like `#!/usr/bin/env rua`, is skipped
+        if self.source.starts_with(b"#!") {
+            while !self.at_end() && self.source[self.current] != b'\n' {
+                self.advance(1);
+            }
+        }
+
         while !self.at_end() {
+            self.col = self.column();
             match self.source[self.current] {
                 b'(' => {
-                    tokens.push(Token::new(self.line, TokenType::LEFTPAREN));
+                    tokens.push(Token::new(self.line, self.col, TokenType::LEFTPAREN));
                     self.advance(1);
                 }
                 b')' => {
-                    tokens.push(Token::new(self.line, TokenType::RIGHTPAREN));
+                    tokens.push(Token::new(self.line, self.col, TokenType::RIGHTPAREN));
                     self.advance(1);
                 }
                 b'[' => {
-                    if let Some(b'[') = self.look_ahead() {
+                    if let Some(level) = self.long_bracket_level(self.current) {
                         // cross line string
-                        match self.lex_long_string() {
+                        match self.lex_long_string(level) {
                             Ok(tok) => tokens.push(tok),
                             Err(e) => return Err(e),
                         }
+                    } else if let Some(b'=') = self.look_ahead() {
+                        return Err(LexError::new(
+                            self.line,
+                            self.col,
+                            String::from("invalid long string delimiter"),
+                        ));
                     } else {
-                        tokens.push(Token::new(self.line, TokenType::LEFTBRACKET));
+                        tokens.push(Token::new(self.line, self.col, TokenType::LEFTBRACKET));
                         self.advance(1);
                     }
                 }
                 b']' => {
-                    tokens.push(Token::new(self.line, TokenType::RIGHTBRACKET));
+                    tokens.push(Token::new(self.line, self.col, TokenType::RIGHTBRACKET));
                     self.advance(1);
                 }
                 b'{' => {
-                    tokens.push(Token::new(self.line, TokenType::LEFTBRACE));
+                    tokens.push(Token::new(self.line, self.col, TokenType::LEFTBRACE));
                     self.advance(1);
                 }
                 b'}' => {
-                    tokens.push(Token::new(self.line, TokenType::RIGHTBRACE));
+                    tokens.push(Token::new(self.line, self.col, TokenType::RIGHTBRACE));
                     self.advance(1);
                 }
                 b',' => {
-                    tokens.push(Token::new(self.line, TokenType::COMMA));
+                    tokens.push(Token::new(self.line, self.col, TokenType::COMMA));
                     self.advance(1);
                 }
 
                 b'+' => {
-                    tokens.push(Token::new(self.line, TokenType::PLUS));
+                    tokens.push(Token::new(self.line, self.col, TokenType::PLUS));
                     self.advance(1);
                 }
                 b'-' => {
@@ -102,100 +123,122 @@ impl<'a> Lexer<'a> {
                             // then check if it is a line comment
                             if !self.lex_line_comment() {
                                 // not a line comment
-                                tokens.push(Token::new(self.line, TokenType::MINUS));
+                                tokens.push(Token::new(self.line, self.col, TokenType::MINUS));
                                 self.advance(1);
                             }
                         }
                     }
                 }
                 b'*' => {
-                    tokens.push(Token::new(self.line, TokenType::MUL));
+                    tokens.push(Token::new(self.line, self.col, TokenType::MUL));
                     self.advance(1);
                 }
                 b'/' => {
                     if let Some(b'/') = self.look_ahead() {
-                        tokens.push(Token::new(self.line, TokenType::FLOORDIV));
+                        tokens.push(Token::new(self.line, self.col, TokenType::FLOORDIV));
                         self.advance(2);
                     } else {
-                        tokens.push(Token::new(self.line, TokenType::DIV));
+                        tokens.push(Token::new(self.line, self.col, TokenType::DIV));
                         self.advance(1);
                     }
                 }
                 b'%' => {
-                    tokens.push(Token::new(self.line, TokenType::MOD));
+                    tokens.push(Token::new(self.line, self.col, TokenType::MOD));
                     self.advance(1);
                 }
                 b'^' => {
-                    tokens.push(Token::new(self.line, TokenType::POW));
+                    tokens.push(Token::new(self.line, self.col, TokenType::POW));
                     self.advance(1);
                 }
                 b'.' => {
-                    if let Some(b'.') = self.look_ahead() {
-                        tokens.push(Token::new(self.line, TokenType::DOTDOT));
+                    if self.source[self.current..].starts_with(b"...") {
+                        tokens.push(Token::new(self.line, self.col, TokenType::DOTDOTDOT));
+                        self.advance(3);
+                    } else if let Some(b'.') = self.look_ahead() {
+                        tokens.push(Token::new(self.line, self.col, TokenType::DOTDOT));
                         self.advance(2);
+                    } else if self.look_ahead().is_some_and(Self::is_digit) {
+                        // a number without its integer part, like `.5`
+                        tokens.push(self.lex_number()?);
                     } else {
-                        tokens.push(Token::new(self.line, TokenType::DOT));
+                        tokens.push(Token::new(self.line, self.col, TokenType::DOT));
                         self.advance(1);
                     }
                 }
                 b'#' => {
-                    tokens.push(Token::new(self.line, TokenType::POUND));
+                    tokens.push(Token::new(self.line, self.col, TokenType::POUND));
                     self.advance(1);
                 }
                 b';' => {
-                    tokens.push(Token::new(self.line, TokenType::SEMICOLON));
+                    tokens.push(Token::new(self.line, self.col, TokenType::SEMICOLON));
+                    self.advance(1);
+                }
+                b':' => {
+                    tokens.push(Token::new(self.line, self.col, TokenType::COLON));
                     self.advance(1);
                 }
 
                 b'=' => {
                     if let Some(b'=') = self.look_ahead() {
-                        tokens.push(Token::new(self.line, TokenType::EQUALEQUAL));
+                        tokens.push(Token::new(self.line, self.col, TokenType::EQUALEQUAL));
                         self.advance(2);
                     } else {
-                        tokens.push(Token::new(self.line, TokenType::EQUAL));
+                        tokens.push(Token::new(self.line, self.col, TokenType::EQUAL));
                         self.advance(1);
                     }
                 }
                 b'~' => {
                     if let Some(b'=') = self.look_ahead() {
-                        tokens.push(Token::new(self.line, TokenType::NOTEQUAL));
+                        tokens.push(Token::new(self.line, self.col, TokenType::NOTEQUAL));
                         self.advance(2);
                     } else {
-                        return Err(LexError::new(
-                            self.line,
-                            String::from("invalid character '~'"),
-                        ));
+                        tokens.push(Token::new(self.line, self.col, TokenType::TILDE));
+                        self.advance(1);
                     }
                 }
                 b'>' => {
                     if let Some(b'=') = self.look_ahead() {
-                        tokens.push(Token::new(self.line, TokenType::GREATEREQUAL));
+                        tokens.push(Token::new(self.line, self.col, TokenType::GREATEREQUAL));
+                        self.advance(2);
+                    } else if let Some(b'>') = self.look_ahead() {
+                        tokens.push(Token::new(self.line, self.col, TokenType::SHR));
                         self.advance(2);
                     } else {
-                        tokens.push(Token::new(self.line, TokenType::GREATER));
+                        tokens.push(Token::new(self.line, self.col, TokenType::GREATER));
                         self.advance(1);
                     }
                 }
                 b'<' => {
                     if let Some(b'=') = self.look_ahead() {
-                        tokens.push(Token::new(self.line, TokenType::LESSEQUAL));
+                        tokens.push(Token::new(self.line, self.col, TokenType::LESSEQUAL));
+                        self.advance(2);
+                    } else if let Some(b'<') = self.look_ahead() {
+                        tokens.push(Token::new(self.line, self.col, TokenType::SHL));
                         self.advance(2);
                     } else {
-                        tokens.push(Token::new(self.line, TokenType::LESS));
+                        tokens.push(Token::new(self.line, self.col, TokenType::LESS));
                         self.advance(1);
                     }
                 }
+                b'&' => {
+                    tokens.push(Token::new(self.line, self.col, TokenType::AMP));
+                    self.advance(1);
+                }
+                b'|' => {
+                    tokens.push(Token::new(self.line, self.col, TokenType::PIPE));
+                    self.advance(1);
+                }
                 // string
                 b'\'' | b'"' => match self.lex_line_string(self.source[self.current]) {
                     Ok(tok) => tokens.push(tok),
                     Err(e) => return Err(e),
                 },
-                // ignore the spaces
-                b' ' | b'\r' | b'\t' => {
+                // ignore the spaces, including vertical tab and form feed
+                b' ' | b'\r' | b'\t' | b'\x0b' | b'\x0c' => {
                     self.advance(1);
                 }
                 b'\n' => {
-                    self.line += 1;
+                    self.newline();
                     self.advance(1);
                 }
 
@@ -208,18 +251,27 @@ impl<'a> Lexer<'a> {
                             Err(e) => return Err(e),
                         }
                     } else if Self::is_alpha_or_underscore(self.source[self.current]) {
-                        tokens.push(self.lex_keyword_or_identifier().unwrap())
+                        tokens.push(self.lex_keyword_or_identifier()?)
                     } else {
+                        let c = self.source[self.current];
+                        // control bytes are shown by their code, like Lua does
+                        let symbol = if c.is_ascii_control() {
+                            format!("<\\{}>", c)
+                        } else {
+                            (c as char).to_string()
+                        };
                         return Err(LexError::new(
                             self.line,
-                            format!("unexpected symbol {}", self.source[self.current] as char),
+                            self.col,
+                            format!("unexpected symbol near '{}'", symbol),
                         ));
                     }
                 }
             }
         }
 
-        tokens.push(Token::new(self.line, TokenType::EOF));
+        self.col = self.column();
+        tokens.push(Token::new(self.line, self.col, TokenType::EOF));
 
         Ok(tokens)
     }
@@ -228,6 +280,23 @@ impl<'a> Lexer<'a> {
         self.current += step;
     }
 
+    /// the source text from `start` up to `end`, which must be valid UTF-8
+    fn text(&self, start: usize, end: usize) -> Result<String, LexError> {
+        String::from_utf8(self.source[start..end].to_vec())
+            .map_err(|_| LexError::new(self.line,self.col, String::from("invalid UTF-8 sequence")))
+    }
+
+    /// count the newline under `current`
+    fn newline(&mut self) {
+        self.line += 1;
+        self.line_start = self.current + 1;
+    }
+
+    /// the column of `current`, starting from 1
+    fn column(&self) -> usize {
+        self.current - self.line_start + 1
+    }
+
     /// check if we are in the end state 
     fn at_end(&self) -> bool {
         self.current >= self.source.len()
@@ -252,105 +321,162 @@ impl<'a> Lexer<'a> {
             panic!("invalid quote argument");
         }
 
-        // mark the start of the string
-        let start = self.current;
-
+        // the bytes of the string, and where the text since the last escape sequence starts
+        let mut value = Vec::new();
         self.advance(1);
+        let mut start = self.current;
+
         while !self.at_end() {
             let c = self.source[self.current];
             if c == b'\n' {
                 // unfinished string
-                return Err(LexError::new(self.line, String::from("unfinished string")));
-            } else if c == b'\'' && quote == b'\'' {
-                // end of the string
-                self.advance(1);
-                break;
-            } else if c == b'"' && quote == b'"' {
+                return Err(LexError::new(self.line,self.col, String::from("unfinished string")));
+            } else if c == quote {
                 // end of the string
+                value.extend(self.text(start, self.current)?.into_bytes());
                 self.advance(1);
                 break;
             } else if c == b'\\' {
-                // match escape characters
-                match self.look_ahead() {
-                    Some(b'n') => {
-                        self.source.remove(self.current);
-                        self.source[self.current] = b'\n';
-                        self.advance(2);
-                    }
-                    Some(b't') => {
-                        self.source.remove(self.current);
-                        self.source[self.current] = b'\t';
-                        self.advance(2);
-                    }
-                    Some(b'\\') => {
-                        self.source.remove(self.current);
-                        self.source[self.current] = b'\\';
-                        self.advance(2);
-                    }
-                    Some(b'\'') => {
-                        self.source.remove(self.current);
-                        self.source[self.current] = b'\'';
-                        self.advance(2);
-                    }
-                    Some(b'"') => {
-                        self.source.remove(self.current);
-                        self.source[self.current] = b'"';
-                        self.advance(2);
-                    }
-                    _ => self.advance(1),
-                }
+                // the text must be UTF-8, but an escape sequence stands for any byte
+                value.extend(self.text(start, self.current)?.into_bytes());
+                let (byte, len) = self.lex_escape()?;
+                value.push(byte);
+                self.advance(len);
+                start = self.current;
             } else {
                 self.advance(1);
             }
         }
 
-        Ok(Token::new(
+        Ok(Token::new(self.line, self.col, TokenType::STRING { value }))
+    }
+
+    /// lex the escape sequence starting at the backslash under `current`,
+    /// returns the byte it stands for and the length of the sequence
+    fn lex_escape(&self) -> Result<(u8, usize), LexError> {
+        let byte = match self.look_ahead() {
+            Some(b'n') => b'\n',
+            Some(b't') => b'\t',
+            Some(b'r') => b'\r',
+            Some(b'a') => b'\x07',
+            Some(b'b') => b'\x08',
+            Some(b'f') => b'\x0c',
+            Some(b'v') => b'\x0b',
+            Some(b'\\') => b'\\',
+            Some(b'\'') => b'\'',
+            Some(b'"') => b'"',
+            // \ddd, up to three decimal digits
+            Some(c) if Self::is_digit(c) => {
+                let digits = self.source[self.current + 1..]
+                    .iter()
+                    .take(3)
+                    .take_while(|c| Self::is_digit(**c))
+                    .count();
+                let end = self.current + 1 + digits;
+                let value = self.source[self.current + 1..end]
+                    .iter()
+                    .fold(0, |acc, c| acc * 10 + (c - b'0') as u32);
+                return match u8::try_from(value) {
+                    Ok(byte) => Ok((byte, digits + 1)),
+                    Err(_) => Err(self.escape_error("decimal escape too large", end)),
+                };
+            }
+            // \xHH, exactly two hexadecimal digits
+            Some(b'x') => {
+                let start = self.current + 2;
+                let mut value = 0;
+                for i in start..start + 2 {
+                    match self.source.get(i).and_then(|c| (*c as char).to_digit(16)) {
+                        Some(d) => value = value * 16 + d as u8,
+                        None => {
+                            let end = (i + 1).min(self.source.len());
+                            return Err(self.escape_error("hexadecimal digit expected", end));
+                        }
+                    }
+                }
+                return Ok((value, 4));
+            }
+            _ => {
+                let end = (self.current + 2).min(self.source.len());
+                return Err(self.escape_error("invalid escape sequence", end));
+            }
+        };
+        Ok((byte, 2))
+    }
+
+    /// an error about the escape sequence from `current` up to `end`, at its column
+    fn escape_error(&self, message: &str, end: usize) -> LexError {
+        LexError::new(
             self.line,
-            TokenType::STRING {
-                value: String::from_utf8(self.source[start + 1..self.current - 1].to_vec())
-                    .unwrap(),
-            },
-        ))
+            self.column(),
+            format!(
+                "{} '{}'",
+                message,
+                String::from_utf8_lossy(&self.source[self.current..end])
+            ),
+        )
     }
 
-    fn lex_long_string(&mut self) -> Result<Token, LexError> {
-        let start = self.current;
-        self.advance(2);
+    /// the level of the opening long bracket at `pos`, that is the number
+    /// of `=` in `[==[`, or `None` if there is no opening long bracket
+    fn long_bracket_level(&self, pos: usize) -> Option<usize> {
+        if self.source.get(pos) != Some(&b'[') {
+            return None;
+        }
+        let level = self.source[pos + 1..]
+            .iter()
+            .take_while(|c| **c == b'=')
+            .count();
+        match self.source.get(pos + 1 + level) {
+            Some(b'[') => Some(level),
+            _ => None,
+        }
+    }
+
+    /// check if the closing long bracket of `level`, like `]==]`, is under `current`
+    fn closes_long_bracket(&self, level: usize) -> bool {
+        let rest = &self.source[self.current..];
+        rest.len() >= level + 2
+            && rest[0] == b']'
+            && rest[1..level + 1].iter().all(|c| *c == b'=')
+            && rest[level + 1] == b']'
+    }
+
+    /// lex a long string enclosed by long brackets of `level`, e.g. `[==[ ... ]==]`,
+    /// brackets of other levels inside it are part of the string
+    fn lex_long_string(&mut self, level: usize) -> Result<Token, LexError> {
+        let first_line = self.line;
+        self.advance(level + 2);
 
-        // the new line character immediately following '[[' is ignored
+        // the new line character immediately following the opening bracket is ignored
         if !self.at_end() && self.source[self.current] == b'\n' {
-            self.source.remove(self.current);
+            self.newline();
+            self.advance(1);
         }
+        let start = self.current;
 
         while !self.at_end() {
-            if self.source[self.current] == b']' {
-                // check end of string
-                if let Some(b']') = self.look_ahead() {
-                    self.advance(2);
-                    return Ok(Token::new(
-                        self.line,
-                        TokenType::STRING {
-                            value: String::from_utf8(
-                                self.source[start + 2..self.current - 2].to_vec(),
-                            )
-                            .unwrap(),
-                        },
-                    ));
-                }
-            } else {
-                // the line number may increase inside long string
-                // so udpate it here
-                if self.source[self.current] == b'\n' {
-                    self.line += 1;
-                }
-                self.advance(1);
+            if self.closes_long_bracket(level) {
+                let value = self.text(start, self.current)?.into_bytes();
+                self.advance(level + 2);
+                return Ok(Token::new(self.line, self.col, TokenType::STRING { value }));
             }
+            // the line number may increase inside long string
+            // so udpate it here
+            if self.source[self.current] == b'\n' {
+                self.newline();
+            }
+            self.advance(1);
         }
 
-        // unterminated string
+        // unterminated string, reported at the end of the source
         Err(LexError::new(
             self.line,
-            String::from("unterminated string"),
+            self.column(),
+            format!(
+                "unfinished long string (starting at line {}) near '<eof>'",
+                first_line
+            ),
         ))
     }
 
@@ -358,34 +484,25 @@ impl<'a> Lexer<'a> {
     /// 
     /// `res == Err(LexError)` if it is not closed. Otherwise, `res == ()`.
     fn lex_long_comment(&mut self) -> Option<Result<(), LexError>> {
-        // check if the start of comment: --[[
-        if self.current + 3 >= self.source.len() {
+        // check if the start of comment: --[[, or --[==[ with a level
+        if self.source.get(self.current + 1) != Some(&b'-') {
             return None;
-        } else if self.source[self.current + 1] != b'-'
-            || self.source[self.current + 2] != b'['
-            || self.source[self.current + 3] != b'['
-        {
-            return None;
-        } else {
-            self.advance(4);
         }
+        let level = self.long_bracket_level(self.current + 2)?;
+        self.advance(level + 4);
         // this is a long comment
         let mut closed = false;
-        let start = self.current;
+        let start = self.line;
 
         while !self.at_end() {
-            if self.source[self.current] == b']' {
-                // check end of string: ]]
-                if let Some(b']') = self.look_ahead() {
-                    self.advance(2);
-                    closed = true;
-                    break;
-                } else {
-                    self.advance(1);
-                }
+            if self.closes_long_bracket(level) {
+                // end of the comment: ]], or ]==] of the same level
+                self.advance(level + 2);
+                closed = true;
+                break;
             } else {
                 if self.source[self.current] == b'\n' {
-                    self.line += 1;
+                    self.newline();
                 }
                 self.advance(1);
             }
@@ -396,7 +513,8 @@ impl<'a> Lexer<'a> {
         } else {
             Some(Err(LexError::new(
                 self.line,
-                format!("unfinished long comment (starting at line {})", start),
+                self.column(),
+                format!("unfinished long comment (starting at line {}) near '<eof>'", start),
             )))
         }
     }
@@ -408,36 +526,34 @@ impl<'a> Lexer<'a> {
     /// otherwise, return false and leave the state unchanged.
     fn lex_line_comment(&mut self) -> bool {
         // check the start of the comment
-        if self.current + 1 >= self.source.len() {
-            return false;
-        } else if self.source[self.current + 1] != b'-' {
+        if self.current + 1 >= self.source.len() || self.source[self.current + 1] != b'-' {
             return false;
         } else {
             self.advance(2);
         }
-        // this is a line comment
+        // this is a line comment, it ends before the newline,
+        // which the main loop counts like any other
 
-        while !self.at_end() {
-            // check the end
-            if self.source[self.current] == b'\n' {
-                self.advance(1);
-                break;
-            }
+        while !self.at_end() && self.source[self.current] != b'\n' {
             self.advance(1);
         }
 
-        self.line += 1;
         true
     }
 
     fn is_digit(c: u8) -> bool {
-        b'0' <= c && c <= b'9'
+        c.is_ascii_digit()
     }
 
-    /// number ::= (digit+) | (digit+ '.' digit+)
+    /// number ::= (digit+ ['.' digit+] | '.' digit+) [('e' | 'E') ['+' | '-'] digit+]
+    ///          | '0x' (hexdigit+ ['.' hexdigit*] | '.' hexdigit+) [('p' | 'P') ['+' | '-'] digit+]
     fn lex_number(&mut self) -> Result<Token, LexError> {
         let start = self.current;
 
+        if self.source[start] == b'0' && matches!(self.look_ahead(), Some(b'x' | b'X')) {
+            return self.lex_hex_number();
+        }
+
         while !self.at_end() && Self::is_digit(self.source[self.current]) {
             self.advance(1);
         }
@@ -454,15 +570,130 @@ impl<'a> Lexer<'a> {
             }
         }
 
-        let num_str = String::from_utf8(self.source[start..self.current].to_vec()).unwrap();
+        // an optional exponent, as in 1e3 or 2.5E-4, makes it a float
+        let mut float = false;
+        if !self.at_end() && matches!(self.source[self.current], b'e' | b'E') {
+            float = true;
+            self.advance(1);
+            if !self.at_end() && matches!(self.source[self.current], b'+' | b'-') {
+                self.advance(1);
+            }
+            if self.at_end() || !Self::is_digit(self.source[self.current]) {
+                let mut end = self.current;
+                while end < self.source.len() && self.source[end].is_ascii_alphanumeric() {
+                    end += 1;
+                }
+                return Err(LexError::new(
+                    self.line,
+                    self.col,
+                    format!(
+                        "malformed number near '{}'",
+                        String::from_utf8_lossy(&self.source[start..end])
+                    ),
+                ));
+            }
+            while !self.at_end() && Self::is_digit(self.source[self.current]) {
+                self.advance(1);
+            }
+        }
+
+        let num_str = self.text(start, self.current)?;
+        // a numeral without a fractional part or an exponent is an integer,
+        // unless it does not fit in one
+        if !float {
+            if let Ok(value) = num_str.parse::<i64>() {
+                return Ok(Token::new(self.line, self.col, TokenType::INTEGER { value }));
+            }
+        }
         match num_str.parse::<f64>() {
-            Ok(value) => Ok(Token::new(self.line, TokenType::NUMBER { value })),
-            Err(e) => Err(LexError::new(self.line, format!("invalid number: {}", e))),
+            Ok(value) => Ok(Token::new(self.line, self.col, TokenType::NUMBER { value })),
+            Err(e) => Err(LexError::new(self.line,self.col, format!("invalid number: {}", e))),
         }
     }
 
+    /// hexadecimal numerals are integers, wrapping around when they do not fit, e.g. `0x1F`,
+    /// unless they have a fractional part or a binary exponent, e.g. `0x1.8p3` is the float 12.0
+    fn lex_hex_number(&mut self) -> Result<Token, LexError> {
+        let start = self.current;
+        // skip '0x'
+        self.advance(2);
+
+        let mut value: i64 = 0;
+        // the float value of the digits, and the power of 2 that scales it
+        let mut mantissa = 0.0;
+        let mut exponent: i64 = 0;
+        let mut float = false;
+        let mut digits = 0;
+        while !self.at_end() {
+            let c = self.source[self.current];
+            if c == b'.' && !float {
+                float = true;
+            } else if let Some(d) = (c as char).to_digit(16) {
+                value = value.wrapping_mul(16).wrapping_add(d as i64);
+                mantissa = mantissa * 16.0 + d as f64;
+                if float {
+                    exponent -= 4;
+                }
+                digits += 1;
+            } else {
+                break;
+            }
+            self.advance(1);
+        }
+        if digits > 0 && !self.at_end() && matches!(self.source[self.current], b'p' | b'P') {
+            let mark = self.current;
+            self.advance(1);
+            let sign = match self.source.get(self.current) {
+                Some(b'-') => -1,
+                Some(b'+') => 1,
+                _ => 0,
+            };
+            if sign != 0 {
+                self.advance(1);
+            }
+            let mut power: i64 = 0;
+            let mut power_digits = 0;
+            while !self.at_end() && Self::is_digit(self.source[self.current]) {
+                power = power.saturating_mul(10).saturating_add((self.source[self.current] - b'0') as i64);
+                power_digits += 1;
+                self.advance(1);
+            }
+            if power_digits == 0 {
+                // not an exponent, the numeral is malformed below
+                self.current = mark;
+            } else {
+                float = true;
+                exponent = exponent.saturating_add(if sign < 0 { -power } else { power });
+            }
+        }
+
+        // a numeral runs until the first byte that cannot be part of a name
+        let mut end = self.current;
+        while end < self.source.len()
+            && (self.source[end].is_ascii_alphanumeric() || self.source[end] == b'_')
+        {
+            end += 1;
+        }
+        if digits == 0 || end > self.current {
+            return Err(LexError::new(
+                self.line,
+                self.col,
+                format!(
+                    "malformed number near '{}'",
+                    String::from_utf8_lossy(&self.source[start..end])
+                ),
+            ));
+        }
+
+        if float {
+            let value = mantissa * 2f64.powi(exponent.clamp(-2000, 2000) as i32);
+            return Ok(Token::new(self.line, self.col, TokenType::NUMBER { value }));
+        }
+        Ok(Token::new(self.line, self.col, TokenType::INTEGER { value }))
+    }
+
     fn is_alpha_or_underscore(c: u8) -> bool {
-        c >= b'a' && c <= b'z' || c >= b'A' && c <= b'Z' || c == b'_'
+        c.is_ascii_lowercase() || c.is_ascii_uppercase() || c == b'_'
     }
 
     fn lex_keyword_or_identifier(&mut self) -> Result<Token, LexError> {
@@ -475,12 +706,12 @@ impl<'a> Lexer<'a> {
             self.advance(1);
         }
 
-        let lexeme = String::from_utf8(self.source[start..self.current].to_vec()).unwrap();
+        let lexeme = self.text(start, self.current)?;
 
         // decide if the lexeme is a keyword or not 
         match self.keywords.get(lexeme.as_str()) {
-            Some(keyword) => Ok(Token::new(self.line, keyword.clone())),
-            None => Ok(Token::new(self.line, TokenType::NAME { value: lexeme })),
+            Some(keyword) => Ok(Token::new(self.line, self.col, keyword.clone())),
+            None => Ok(Token::new(self.line, self.col, TokenType::NAME { value: lexeme })),
         }
     }
 }
@@ -489,16 +720,21 @@ impl<'a> Lexer<'a> {
 pub struct LexError {
     message: String,
     line: usize,
+    col: usize,
 }
 
 impl LexError {
-    fn new(line: usize, message: String) -> Self {
-        Self { line, message }
+    fn new(line: usize, col: usize, message: String) -> Self {
+        Self { line, col, message }
     }
 }
 
 impl RuaError for LexError {
-    fn report(&self, filename: &str) {
-        eprintln!("rua: {}:{}: {}", filename, self.line, self.message);
+    fn message(&self, filename: &str) -> String {
+        format!("{}:{}:{}: {}", filename, self.line, self.col, self.message)
+    }
+
+    fn incomplete(&self) -> bool {
+        self.message.ends_with("near '<eof>'")
     }
 }
@@ -0,0 +1,23 @@
+//! rua, an interpreter for a subset of Lua 5.4, as a library for programs embedding it
+//!
+//! a host runs a chunk with `Rua::run_str` or `Rua::run_file`, or builds a `Rua` from source
+//! to register its own global functions and redirect the input and output of the chunk first
+#![allow(clippy::upper_case_acronyms, clippy::enum_variant_names)]
+
+mod ast;
+mod builtin;
+mod environment;
+mod interpreter;
+mod lexer;
+mod parser;
+mod pattern;
+pub mod repl;
+mod rua;
+mod token;
+mod value;
+
+pub use crate::{
+    interpreter::{Interpreter, RuntimeException},
+    rua::{Rua, RuaError},
+    value::{HostFn, Value},
+};
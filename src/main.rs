@@ -1,46 +1,85 @@
-mod ast;
-mod environment;
-mod interpreter;
-mod lexer;
-mod parser;
-mod rua;
-mod token;
-mod value;
+use std::{
+    env,
+    fs::read,
+    io::{self, Read},
+    process::exit,
+    time::Instant,
+};
 
-use std::{env, process::exit, fs::read_to_string};
-
-use rua::{Rua, RuaError};
+use rua::{repl, Rua, RuaError};
 
 fn main() {
-    let args: Vec<String> = env::args().collect();
-    if args.len() != 2 {
-        eprintln!("Usage: rua [filename]");
+    // the flags can be given before or after the file name:
+    // --time prints how long each phase took to stderr,
+    // --dump-ast prints the parsed program instead of running it,
+    // --tokens prints the tokens of the program instead of parsing it
+    let (flags, args): (Vec<String>, Vec<String>) =
+        env::args().skip(1).partition(|arg| arg.starts_with("--"));
+    let time = flags.iter().any(|flag| flag == "--time");
+    let dump_ast = flags.iter().any(|flag| flag == "--dump-ast");
+    let dump_tokens = flags.iter().any(|flag| flag == "--tokens");
+    if let Some(flag) = flags
+        .iter()
+        .find(|flag| !matches!(flag.as_str(), "--time" | "--dump-ast" | "--tokens"))
+    {
+        eprintln!("rua: unrecognized option '{}'", flag);
+        exit(1);
+    }
+    // without a script, read statements from stdin
+    if args.is_empty() && flags.is_empty() {
+        repl::run();
+        return;
+    }
+    if args.len() != 1 {
+        eprintln!("Usage: rua [--time] [--dump-ast] [--tokens] [filename | -]");
         exit(1);
     }
 
-    let s = read_to_string(&args[1]);
+    // the source is read as bytes, the lexer reports any text that is not valid UTF-8;
+    // a script named `-` is read from stdin, and its errors are reported as in it
+    let (s, chunkname) = if args[0] == "-" {
+        let mut source = Vec::new();
+        let s = io::stdin().read_to_end(&mut source).map(|_| source);
+        (s, "stdin")
+    } else {
+        (read(&args[0]), args[0].as_str())
+    };
 
     match s {
         Ok(s) => {
-            // replace \r\n with \n
-            let source = s.replace("\r\n", "\n").into_bytes();
+            let mut program = Rua::new(s);
 
-            let mut program = Rua::new(source);
+            let start = Instant::now();
+            let tokens = program.lex();
+            let lexed = Instant::now();
+            if dump_tokens {
+                match tokens {
+                    Ok(tokens) => tokens.iter().for_each(|token| println!("{}", token)),
+                    Err(e) => e.report(chunkname),
+                }
+                return;
+            }
+            let block = tokens.map(|tokens| program.parse(tokens));
+            let parsed = Instant::now();
 
-            match program.lex() {
-                Ok(tokens) => match program.parse(tokens) {
-                    Ok(block) => match program.interpret(block) {
-                        Ok(()) => {}
-                        Err(e) => e.report(&args[1]),
-                    },
-                    Err(e) => {
-                        e.report(&args[1]);
+            match block {
+                Ok(Ok(block)) if dump_ast => print!("{}", block),
+                Ok(Ok(block)) => {
+                    let res = program.interpret(block, chunkname);
+                    if time {
+                        eprintln!("lex:       {:?}", lexed - start);
+                        eprintln!("parse:     {:?}", parsed - lexed);
+                        eprintln!("interpret: {:?}", parsed.elapsed());
+                    }
+                    if let Err(e) = res {
+                        e.report(chunkname);
                     }
-                },
-                Err(e) => e.report(&args[1]),
+                }
+                Ok(Err(e)) => e.report(chunkname),
+                Err(e) => e.report(chunkname),
             }
         },
-        Err(e) => eprintln!("{}", e),
+        Err(e) => e.report(chunkname),
     }
     
 
@@ -1,5 +1,7 @@
+use std::rc::Rc;
+
 use crate::{
-    ast::{Block, Exp, ExpList, Field, FieldList, FuncBody, NameList, Stmt, Var, VarList},
+    ast::{Block, Exp, ExpList, Field, FieldList, FuncBody, Name, NameList, Stmt, Var, VarList},
     rua::RuaError,
     token::{
         Token,
@@ -16,6 +18,7 @@ macro_rules! consume {
         } else {
             Err(ParseError::new(
                 tok.line,
+                tok.col,
                 format!(
                     "unexpected token '{}', expect '{}'",
                     tok.tok_type, $expected_expr
@@ -28,7 +31,11 @@ macro_rules! consume {
 pub struct Parser {
     tokens: Vec<Token>,
     current: usize,
+    // the position of the next token
     line: usize,
+    col: usize,
+    // whether each enclosing function is variadic, the main chunk is
+    vararg: Vec<bool>,
 }
 
 impl Parser {
@@ -37,6 +44,8 @@ impl Parser {
             tokens,
             current: 0,
             line: 1,
+            col: 1,
+            vararg: vec![true],
         }
     }
 
@@ -46,6 +55,7 @@ impl Parser {
         if !self.at_end() {
             return Err(ParseError::new(
                 self.line,
+                self.col,
                 format!("unexpected symbol '{}'", self.peek().tok_type),
             ));
         }
@@ -91,10 +101,22 @@ impl Parser {
                         Exp::FunctionCall {
                             prefixexp,
                             arguments,
+                            line,
                         } => statements.push(Stmt::FunctionCall {
                             prefixexp,
                             arguments,
-                            line: self.line,
+                            line,
+                        }),
+                        Exp::MethodCall {
+                            prefixexp,
+                            name,
+                            arguments,
+                            line,
+                        } => statements.push(Stmt::MethodCall {
+                            prefixexp,
+                            name,
+                            arguments,
+                            line,
                         }),
                         // the returned value can only be Exp::Grouping, which is impossible
                         // because it starts with a name, not a LEFTPAREN.
@@ -102,6 +124,7 @@ impl Parser {
                         _ => {
                             return Err(ParseError::new(
                                 self.peek().line,
+                                self.peek().col,
                                 format!("syntax error near {}", self.peek().tok_type),
                             ))
                         }
@@ -110,8 +133,9 @@ impl Parser {
 
                 // break
                 BREAK => {
+                    let line = self.peek().line;
                     self.advance();
-                    statements.push(Stmt::Break { line: self.line });
+                    statements.push(Stmt::Break { line });
                 }
 
                 // do block end
@@ -130,6 +154,11 @@ impl Parser {
                     statements.push(self.parse_while()?);
                 }
 
+                // repeat block until exp
+                REPEAT => {
+                    statements.push(self.parse_repeat()?);
+                }
+
                 // if exp then block {elseif exp then block} [else block] end
                 IF => {
                     statements.push(self.parse_if()?);
@@ -155,7 +184,8 @@ impl Parser {
                         _ => {
                             return Err(ParseError::new(
                                 self.peek().line,
-                                format!("<name> expected after 'local'"),
+                                self.peek().col,
+                                "<name> expected after 'local'".to_string(),
                             ))
                         }
                     }
@@ -210,6 +240,20 @@ impl Parser {
         })
     }
 
+    /// repeat block until exp
+    fn parse_repeat(&mut self) -> Result<Stmt, ParseError> {
+        consume!(self.advance(), REPEAT, REPEAT)?;
+        let body = self.parse_block()?;
+        consume!(self.advance(), UNTIL, UNTIL)?;
+        let condition = self.parse_expression()?;
+
+        Ok(Stmt::RepeatUntil {
+            body,
+            condition,
+            line: self.line,
+        })
+    }
+
     /// if exp then block (elseif exp then block)* (else block)? end
     fn parse_if(&mut self) -> Result<Stmt, ParseError> {
         // if exp then block
@@ -252,6 +296,8 @@ impl Parser {
     }
 
     fn parse_for(&mut self) -> Result<Stmt, ParseError> {
+        // errors of the loop header (e.g. a zero step) are reported at 'for'
+        let line = self.peek().line;
         consume!(self.advance(), FOR, FOR)?;
         match self.peek().tok_type {
             NAME { value } => {
@@ -269,9 +315,9 @@ impl Parser {
                                 self.advance();
                                 self.parse_expression()?
                             }
-                            // no given step value, use the default 1.0
+                            // no given step value, use the default 1
                             _ => Exp::Literal {
-                                value: Token::new(self.line, NUMBER { value: 1.0 }),
+                                value: Token::new(self.line, self.col, INTEGER { value: 1 }),
                             },
                         };
 
@@ -285,51 +331,34 @@ impl Parser {
                             end,
                             step,
                             body,
-                            line: self.line,
+                            line,
                         })
                     }
 
                     _ => {
                         // generic for
-                        // for namelist in pairs(exp) do block end
+                        // for namelist in explist do block end
                         let namelist = self.parse_namelist()?;
                         consume!(self.advance(), IN, IN)?;
+                        let explist = self.parse_explist()?;
 
-                        if let NAME { value } = self.peek().tok_type {
-                            if value == "pairs" {
-                                self.advance();
-                                consume!(self.advance(), LEFTPAREN, LEFTPAREN)?;
-                                let table = self.parse_expression()?;
-                                consume!(self.advance(), RIGHTPAREN, RIGHTPAREN)?;
-
-                                consume!(self.advance(), DO, DO)?;
-                                let body = self.parse_block()?;
-                                consume!(self.advance(), END, END)?;
-
-                                Ok(Stmt::GenericFor {
-                                    namelist,
-                                    table,
-                                    body,
-                                    line: self.line,
-                                })
-                            } else {
-                                return Err(ParseError::new(
-                                    self.peek().line,
-                                    format!("'pairs' expected after 'in'"),
-                                ));
-                            }
-                        } else {
-                            return Err(ParseError::new(
-                                self.peek().line,
-                                format!("'pairs' expected after 'in'"),
-                            ));
-                        }
+                        consume!(self.advance(), DO, DO)?;
+                        let body = self.parse_block()?;
+                        consume!(self.advance(), END, END)?;
+
+                        Ok(Stmt::GenericFor {
+                            namelist,
+                            explist,
+                            body,
+                            line,
+                        })
                     }
                 }
             }
 
             _ => Err(ParseError::new(
                 self.line,
+                self.col,
                 format!("<name> expected near {}", self.peek().tok_type),
             )),
         }
@@ -348,7 +377,7 @@ impl Parser {
             }
 
             // no return values
-            END | ELSE | ELSEIF => Ok(Stmt::RetStmt {
+            END | ELSE | ELSEIF | UNTIL => Ok(Stmt::RetStmt {
                 explist: ExpList(vec![]),
                 line: self.line,
             }),
@@ -366,37 +395,97 @@ impl Parser {
         }
     }
 
-    /// ('local')? function Name funcbody
+    /// local function Name funcbody | function funcname funcbody
+    /// 
+    /// funcname -> Name ('.' Name)* (':' Name)?
+    /// 
+    /// funcbody -> '(' parlist? ')' block end
     /// 
-    /// funcbody -> '(' namelist? ')' block end
+    /// `function t.a:m() body end` is `t.a.m = function(self) body end`
     fn parse_function_decl(&mut self, local: bool) -> Result<Stmt, ParseError> {
+        let line = self.peek().line;
         consume!(self.advance(), FUNCTION, FUNCTION)?;
         match self.peek().tok_type {
             NAME { value } => {
                 self.advance();
+                let mut var = Var::Name {
+                    name: value.clone(),
+                };
+                let mut is_field = false;
+                let mut is_method = false;
+                while !local && !is_method {
+                    match self.peek().tok_type {
+                        DOT => {}
+                        COLON => is_method = true,
+                        _ => break,
+                    }
+                    let line = self.line;
+                    self.advance();
+                    let key = Exp::Literal {
+                        value: Token::new(
+                            self.line,
+                            self.col,
+                            STRING {
+                                value: self.parse_name()?.into_bytes(),
+                            },
+                        ),
+                    };
+                    var = Var::TableIndex {
+                        prefixexp: Box::new(Exp::Var { var }),
+                        exp: Box::new(key),
+                        line,
+                    };
+                    is_field = true;
+                }
+                // Lua does not allow a computed field as the function name,
+                // e.g. `function t[1]() end`; `t[1] = function() end` is the way to go
+                if let LEFTBRACKET = self.peek().tok_type {
+                    return Err(ParseError::new(
+                        self.peek().line,
+                        self.peek().col,
+                        format!("unexpected symbol near '{}'", self.peek().tok_type),
+                    ));
+                }
                 consume!(self.advance(), LEFTPAREN, LEFTPAREN)?;
-                let parlist = if let RIGHTPAREN = self.peek().tok_type {
+                let mut parlist = if let RIGHTPAREN = self.peek().tok_type {
                     NameList(vec![])
                 } else {
-                    self.parse_namelist()?
+                    self.parse_parlist()?
                 };
                 consume!(self.advance(), RIGHTPAREN, RIGHTPAREN)?;
-                let body = self.parse_block()?;
+                if is_method {
+                    parlist.0.insert(0, String::from("self"));
+                }
+                let body = self.parse_funcbody_block(&parlist)?;
                 consume!(self.advance(), END, END)?;
+                if is_field {
+                    return Ok(Stmt::Assign {
+                        left: VarList { vars: vec![var] },
+                        right: ExpList(vec![Exp::Function {
+                            funcbody: FuncBody {
+                                parlist: Rc::new(parlist),
+                                block: Rc::new(body),
+                            },
+                            line,
+                        }]),
+                        line: self.line,
+                    });
+                }
                 Ok(Stmt::FuncDecl {
                     local,
                     name: value,
-                    parlist,
-                    body,
-                    line: self.line,
+                    parlist: Rc::new(parlist),
+                    body: Rc::new(body),
+                    line,
                 })
             }
 
             _ => {
-                return Err(ParseError::new(
+                Err(ParseError::new(
                     self.line,
-                    format!("<name> expected after 'function'"),
-                ));
+                    self.col,
+                    "<name> expected after 'function'".to_string(),
+                ))
             }
         }
     }
@@ -440,12 +529,12 @@ impl Parser {
         Ok(left)
     }
 
-    /// comparison -> concat (('>' | '<' | '<=' | '>=' | '==' | '~=')) concat)*
+    /// comparison -> bitwise_or (('>' | '<' | '<=' | '>=' | '==' | '~=')) bitwise_or)*
     fn parse_comparison(&mut self) -> Result<Exp, ParseError> {
-        let mut left = self.parse_concat()?;
+        let mut left = self.parse_bitwise_or()?;
         while self.peek_comparison() {
             let operator = self.advance();
-            let right = self.parse_concat()?;
+            let right = self.parse_bitwise_or()?;
             left = Exp::Binary {
                 left: Box::new(left),
                 operator,
@@ -456,12 +545,28 @@ impl Parser {
         Ok(left)
     }
 
-    /// concat -> term ('..' term)*
-    fn parse_concat(&mut self) -> Result<Exp, ParseError> {
-        let mut left = self.parse_term()?;
-        while self.peek_concat() {
+    /// bitwise_or -> bitwise_xor ('|' bitwise_xor)*
+    fn parse_bitwise_or(&mut self) -> Result<Exp, ParseError> {
+        let mut left = self.parse_bitwise_xor()?;
+        while self.peek_bitwise_or() {
+            let operator = self.advance();
+            let right = self.parse_bitwise_xor()?;
+            left = Exp::Binary {
+                left: Box::new(left),
+                operator,
+                right: Box::new(right),
+            }
+        }
+
+        Ok(left)
+    }
+
+    /// bitwise_xor -> bitwise_and ('~' bitwise_and)*
+    fn parse_bitwise_xor(&mut self) -> Result<Exp, ParseError> {
+        let mut left = self.parse_bitwise_and()?;
+        while self.peek_bitwise_xor() {
             let operator = self.advance();
-            let right = self.parse_term()?;
+            let right = self.parse_bitwise_and()?;
             left = Exp::Binary {
                 left: Box::new(left),
                 operator,
@@ -472,6 +577,55 @@ impl Parser {
         Ok(left)
     }
 
+    /// bitwise_and -> shift ('&' shift)*
+    fn parse_bitwise_and(&mut self) -> Result<Exp, ParseError> {
+        let mut left = self.parse_shift()?;
+        while self.peek_bitwise_and() {
+            let operator = self.advance();
+            let right = self.parse_shift()?;
+            left = Exp::Binary {
+                left: Box::new(left),
+                operator,
+                right: Box::new(right),
+            }
+        }
+
+        Ok(left)
+    }
+
+    /// shift -> concat (('<<' | '>>') concat)*
+    fn parse_shift(&mut self) -> Result<Exp, ParseError> {
+        let mut left = self.parse_concat()?;
+        while self.peek_shift() {
+            let operator = self.advance();
+            let right = self.parse_concat()?;
+            left = Exp::Binary {
+                left: Box::new(left),
+                operator,
+                right: Box::new(right),
+            }
+        }
+
+        Ok(left)
+    }
+
+    /// concat -> term ('..' concat)?
+    fn parse_concat(&mut self) -> Result<Exp, ParseError> {
+        let left = self.parse_term()?;
+        if self.peek_concat() {
+            // '..' is right associative, a..b..c is a..(b..c)
+            let operator = self.advance();
+            let right = self.parse_concat()?;
+            Ok(Exp::Binary {
+                left: Box::new(left),
+                operator,
+                right: Box::new(right),
+            })
+        } else {
+            Ok(left)
+        }
+    }
+
     /// term -> factor (('-' | '+') factor)*
     fn parse_term(&mut self) -> Result<Exp, ParseError> {
         let mut left = self.parse_factor()?;
@@ -504,7 +658,7 @@ impl Parser {
         Ok(left)
     }
 
-    /// unary -> (not | '-' | '#') unary | power
+    /// unary -> (not | '-' | '#' | '~') unary | power
     fn parse_unary(&mut self) -> Result<Exp, ParseError> {
         if self.peek_unary() {
             let operator = self.advance();
@@ -518,41 +672,60 @@ impl Parser {
         }
     }
 
-    /// power -> primary ('^' primary)*
+    /// power -> primary ('^' unary)?
+    ///
+    /// '^' is right associative and binds tighter than the unary operators on its left,
+    /// but its exponent can be a unary expression: 2^-2 is 2^(-2), -2^2 is -(2^2)
     fn parse_power(&mut self) -> Result<Exp, ParseError> {
-        let mut left = self.parse_primary()?;
-        while self.peek_power() {
+        let left = self.parse_primary()?;
+        if self.peek_power() {
             let operator = self.advance();
-            let right = self.parse_primary()?;
-            left = Exp::Binary {
+            let right = self.parse_unary()?;
+            Ok(Exp::Binary {
                 left: Box::new(left),
                 operator,
                 right: Box::new(right),
-            }
+            })
+        } else {
+            Ok(left)
         }
-
-        Ok(left)
     }
 
-    /// primary -> functiondef | tableconstructor | prefixexp
+    /// primary -> functiondef | tableconstructor | '...' | prefixexp
     fn parse_primary(&mut self) -> Result<Exp, ParseError> {
         match self.peek().tok_type {
             LEFTBRACE => self.parse_table_constructor(),
 
             FUNCTION => self.parse_function(),
 
+            DOTDOTDOT => {
+                if let Some(false) = self.vararg.last() {
+                    return Err(ParseError::new(
+                        self.peek().line,
+                        self.peek().col,
+                        String::from("cannot use '...' outside a vararg function near '...'"),
+                    ));
+                }
+                self.advance();
+                Ok(Exp::Vararg)
+            }
+
             _ => self.parse_prefixexp(),
         }
     }
 
-    /// prefixexp -> Name (('[' exp ']') | args | ('.' Name) )*
+    /// ```text
+    /// prefixexp -> Name (('[' exp ']') | args | ('.' Name) | (':' Name args) )*
     /// 
-    ///            | '(' exp ')' (('[' exp ']') | args | ('.' Name) )*
+    ///            | '(' exp ')' (('[' exp ']') | args | ('.' Name) | (':' Name args) )*
     /// 
     ///            | literal
     ///
     /// args -> '(' [explist] ')' | tableconstructor | String
+    /// ```
     fn parse_prefixexp(&mut self) -> Result<Exp, ParseError> {
+        // calls are reported at the line where the prefixexp starts
+        let line = self.peek().line;
         match self.peek().tok_type {
             // start with grouping
             // prefixexp -> '(' exp ')' (('[' exp ']') | args | ('.' Name) )*
@@ -565,6 +738,7 @@ impl Parser {
                     // (('[' exp ']') | args | '.' Name )*
                     match self.peek().tok_type {
                         LEFTBRACKET => {
+                            let line = self.line;
                             self.advance();
                             let index = self.parse_expression()?;
                             consume!(self.advance(), RIGHTBRACKET, RIGHTBRACKET)?;
@@ -572,24 +746,34 @@ impl Parser {
                                 var: Var::TableIndex {
                                     prefixexp: Box::new(head_exp),
                                     exp: Box::new(index),
+                                    line,
                                 },
                             }
                         }
                         DOT => {
+                            let line = self.line;
                             self.advance();
                             if let NAME { value } = self.peek().tok_type {
                                 let index = Exp::Literal {
-                                    value: Token::new(self.line, STRING { value }),
+                                    value: Token::new(
+                                        self.line,
+                                        self.col,
+                                        STRING {
+                                            value: value.into_bytes(),
+                                        },
+                                    ),
                                 };
                                 head_exp = Exp::Var {
                                     var: Var::TableIndex {
                                         prefixexp: Box::new(head_exp),
                                         exp: Box::new(index),
+                                        line,
                                     },
                                 }
                             } else {
                                 return Err(ParseError::new(
                                     self.peek().line,
+                                    self.peek().col,
                                     format!("<name> expected near '{}'", self.peek().tok_type),
                                 ));
                             }
@@ -607,6 +791,7 @@ impl Parser {
                             head_exp = Exp::FunctionCall {
                                 prefixexp: Box::new(head_exp),
                                 arguments,
+                                line,
                             }
                         }
                         LEFTBRACE => {
@@ -615,6 +800,7 @@ impl Parser {
                             head_exp = Exp::FunctionCall {
                                 prefixexp: Box::new(head_exp),
                                 arguments,
+                                line,
                             }
                         }
                         STRING { value: _ } => {
@@ -623,6 +809,19 @@ impl Parser {
                             head_exp = Exp::FunctionCall {
                                 prefixexp: Box::new(head_exp),
                                 arguments,
+                                line,
+                            }
+                        }
+                        // ':' Name args
+                        COLON => {
+                            self.advance();
+                            let name = self.parse_name()?;
+                            let arguments = self.parse_args()?;
+                            head_exp = Exp::MethodCall {
+                                prefixexp: Box::new(head_exp),
+                                name,
+                                arguments,
+                                line,
                             }
                         }
                         _ => break,
@@ -653,6 +852,7 @@ impl Parser {
                     // (('[' exp ']') | args | ('.' Name))*
                     match self.peek().tok_type {
                         LEFTBRACKET => {
+                            let line = self.line;
                             self.advance();
                             let index = self.parse_expression()?;
                             consume!(self.advance(), RIGHTBRACKET, RIGHTBRACKET)?;
@@ -660,24 +860,34 @@ impl Parser {
                                 var: Var::TableIndex {
                                     prefixexp: Box::new(head_exp),
                                     exp: Box::new(index),
+                                    line,
                                 },
                             }
                         }
                         DOT => {
+                            let line = self.line;
                             self.advance();
                             if let NAME { value } = self.peek().tok_type {
                                 let index = Exp::Literal {
-                                    value: Token::new(self.line, STRING { value }),
+                                    value: Token::new(
+                                        self.line,
+                                        self.col,
+                                        STRING {
+                                            value: value.into_bytes(),
+                                        },
+                                    ),
                                 };
                                 head_exp = Exp::Var {
                                     var: Var::TableIndex {
                                         prefixexp: Box::new(head_exp),
                                         exp: Box::new(index),
+                                        line,
                                     },
                                 }
                             } else {
                                 return Err(ParseError::new(
                                     self.peek().line,
+                                    self.peek().col,
                                     format!("<name> expected near '{}'", self.peek().tok_type),
                                 ));
                             }
@@ -694,6 +904,7 @@ impl Parser {
                             head_exp = Exp::FunctionCall {
                                 prefixexp: Box::new(head_exp),
                                 arguments,
+                                line,
                             }
                         }
                         LEFTBRACE => {
@@ -702,6 +913,7 @@ impl Parser {
                             head_exp = Exp::FunctionCall {
                                 prefixexp: Box::new(head_exp),
                                 arguments,
+                                line,
                             }
                         }
                         STRING { value: _ } => {
@@ -710,6 +922,19 @@ impl Parser {
                             head_exp = Exp::FunctionCall {
                                 prefixexp: Box::new(head_exp),
                                 arguments,
+                                line,
+                            }
+                        }
+                        // ':' Name args
+                        COLON => {
+                            self.advance();
+                            let name = self.parse_name()?;
+                            let arguments = self.parse_args()?;
+                            head_exp = Exp::MethodCall {
+                                prefixexp: Box::new(head_exp),
+                                name,
+                                arguments,
+                                line,
                             }
                         }
                         _ => break,
@@ -733,6 +958,7 @@ impl Parser {
         } else {
             Err(ParseError::new(
                 self.line,
+                self.col,
                 format!("syntax error near '{}'", self.peek().tok_type),
             ))
         }
@@ -775,6 +1001,7 @@ impl Parser {
                 } else {
                     return Err(ParseError::new(
                         self.peek().line,
+                        self.peek().col,
                         String::from("unexpected symbol after ','"),
                     ));
                 }
@@ -786,6 +1013,89 @@ impl Parser {
         Ok(namelist)
     }
 
+    /// Name, e.g. after the '.' or ':' of a funcname or a method call
+    fn parse_name(&mut self) -> Result<Name, ParseError> {
+        if let NAME { value } = self.peek().tok_type {
+            self.advance();
+            Ok(value)
+        } else {
+            Err(ParseError::new(
+                self.peek().line,
+                self.peek().col,
+                format!("<name> expected near '{}'", self.peek().tok_type),
+            ))
+        }
+    }
+
+    /// args -> '(' [explist] ')' | tableconstructor | String
+    fn parse_args(&mut self) -> Result<ExpList, ParseError> {
+        match self.peek().tok_type {
+            LEFTPAREN => {
+                self.advance();
+                let arguments = if let RIGHTPAREN = self.peek().tok_type {
+                    ExpList(vec![])
+                } else {
+                    self.parse_explist()?
+                };
+                consume!(self.advance(), RIGHTPAREN, RIGHTPAREN)?;
+                Ok(arguments)
+            }
+            LEFTBRACE => Ok(ExpList(vec![self.parse_table_constructor()?])),
+            STRING { value: _ } => Ok(ExpList(vec![self.parse_literal()?])),
+            _ => Err(ParseError::new(
+                self.peek().line,
+                self.peek().col,
+                format!("function arguments expected near '{}'", self.peek().tok_type),
+            )),
+        }
+    }
+
+    /// parlist -> namelist (',' '...')? | '...'
+    ///
+    /// '...' is kept as the last name of the list
+    fn parse_parlist(&mut self) -> Result<NameList, ParseError> {
+        let mut parlist = NameList(Vec::new());
+        loop {
+            match self.peek().tok_type {
+                NAME { value } => {
+                    parlist.0.push(value);
+                    self.advance();
+                }
+                DOTDOTDOT => {
+                    parlist.0.push(String::from("..."));
+                    self.advance();
+                    break;
+                }
+                _ => {
+                    return Err(ParseError::new(
+                        self.peek().line,
+                        self.peek().col,
+                        format!("<name> expected near '{}'", self.peek().tok_type),
+                    ))
+                }
+            }
+
+            if let COMMA = self.peek().tok_type {
+                self.advance();
+            } else {
+                break;
+            }
+        }
+
+        Ok(parlist)
+    }
+
+    /// parse the block of a function body,
+    /// recording whether '...' can be used inside it
+    fn parse_funcbody_block(&mut self, parlist: &NameList) -> Result<Block, ParseError> {
+        let is_vararg = parlist.0.last().is_some_and(|name| name == "...");
+        self.vararg.push(is_vararg);
+        let block = self.parse_block();
+        self.vararg.pop();
+
+        block
+    }
+
     /// tableconstructor -> '{' fieldlist? '}'
     fn parse_table_constructor(&mut self) -> Result<Exp, ParseError> {
         consume!(self.advance(), LEFTBRACE, LEFTBRACE)?;
@@ -828,7 +1138,7 @@ impl Parser {
                         exp: self.parse_expression()?,
                     })
                 }
-                _ => Err(ParseError::new(self.line, format!("<name> expected."))),
+                _ => Err(ParseError::new(self.line,self.col, "<name> expected.".to_string())),
             }
         } else {
             // exp
@@ -841,21 +1151,26 @@ impl Parser {
 
     /// functiondef -> function funcbody
     /// 
-    /// funcbody -> '(' namelist? ')' block end
+    /// funcbody -> '(' parlist? ')' block end
     fn parse_function(&mut self) -> Result<Exp, ParseError> {
+        let line = self.peek().line;
         consume!(self.advance(), FUNCTION, FUNCTION)?;
         consume!(self.advance(), LEFTPAREN, LEFTPAREN)?;
         let parlist = if let RIGHTPAREN = self.peek().tok_type {
             NameList(vec![])
         } else {
-            self.parse_namelist()?
+            self.parse_parlist()?
         };
         consume!(self.advance(), RIGHTPAREN, RIGHTPAREN)?;
-        let block = self.parse_block()?;
+        let block = self.parse_funcbody_block(&parlist)?;
         consume!(self.advance(), END, END)?;
 
         Ok(Exp::Function {
-            funcbody: FuncBody { parlist, block },
+            funcbody: FuncBody {
+                parlist: Rc::new(parlist),
+                block: Rc::new(block),
+            },
+            line,
         })
     }
 
@@ -863,7 +1178,7 @@ impl Parser {
     fn parse_literal(&mut self) -> Result<Exp, ParseError> {
         let tok = self.peek();
         match tok.tok_type {
-            NUMBER { value: _ } => {
+            INTEGER { value: _ } | NUMBER { value: _ } => {
                 self.advance();
                 Ok(Exp::Literal { value: tok })
             }
@@ -877,6 +1192,7 @@ impl Parser {
             }
             _ => Err(ParseError::new(
                 tok.line,
+                tok.col,
                 format!("unexpected symbol near '{}'", tok.tok_type),
             )),
         }
@@ -890,6 +1206,7 @@ impl Parser {
         if !self.at_end() {
             self.current += 1;
             self.line = self.peek().line;
+            self.col = self.peek().col;
             self.tokens[self.current - 1].clone()
         } else {
             // return EOF
@@ -906,10 +1223,7 @@ impl Parser {
             return false;
         }
 
-        match self.peek().tok_type {
-            OR => true,
-            _ => false,
-        }
+        matches!(self.peek().tok_type, OR)
     }
 
     fn peek_logic_and(&self) -> bool {
@@ -917,10 +1231,7 @@ impl Parser {
             return false;
         }
 
-        match self.peek().tok_type {
-            AND => true,
-            _ => false,
-        }
+        matches!(self.peek().tok_type, AND)
     }
 
     fn peek_comparison(&self) -> bool {
@@ -928,43 +1239,63 @@ impl Parser {
             return false;
         }
 
-        match self.peek().tok_type {
-            GREATER | LESS | GREATEREQUAL | LESSEQUAL | NOTEQUAL | EQUALEQUAL => true,
-            _ => false,
+        matches!(self.peek().tok_type, GREATER | LESS | GREATEREQUAL | LESSEQUAL | NOTEQUAL | EQUALEQUAL)
+    }
+
+    fn peek_bitwise_or(&self) -> bool {
+        if self.at_end() {
+            return false;
         }
+
+        matches!(self.peek().tok_type, PIPE)
     }
 
-    fn peek_concat(&self) -> bool {
+    fn peek_bitwise_xor(&self) -> bool {
         if self.at_end() {
             return false;
         }
 
-        match self.peek().tok_type {
-            DOTDOT => true,
-            _ => false,
+        matches!(self.peek().tok_type, TILDE)
+    }
+
+    fn peek_bitwise_and(&self) -> bool {
+        if self.at_end() {
+            return false;
         }
+
+        matches!(self.peek().tok_type, AMP)
     }
 
-    fn peek_term(&self) -> bool {
+    fn peek_shift(&self) -> bool {
         if self.at_end() {
             return false;
         }
 
-        match self.peek().tok_type {
-            PLUS | MINUS => true,
-            _ => false,
+        matches!(self.peek().tok_type, SHL | SHR)
+    }
+
+    fn peek_concat(&self) -> bool {
+        if self.at_end() {
+            return false;
         }
+
+        matches!(self.peek().tok_type, DOTDOT)
     }
 
-    fn peek_factor(&self) -> bool {
+    fn peek_term(&self) -> bool {
         if self.at_end() {
             return false;
         }
 
-        match self.peek().tok_type {
-            MUL | DIV | FLOORDIV | MOD => true,
-            _ => false,
+        matches!(self.peek().tok_type, PLUS | MINUS)
+    }
+
+    fn peek_factor(&self) -> bool {
+        if self.at_end() {
+            return false;
         }
+
+        matches!(self.peek().tok_type, MUL | DIV | FLOORDIV | MOD)
     }
 
     fn peek_unary(&self) -> bool {
@@ -972,10 +1303,7 @@ impl Parser {
             return false;
         }
 
-        match self.peek().tok_type {
-            MINUS | NOT | POUND => true,
-            _ => false,
-        }
+        matches!(self.peek().tok_type, MINUS | NOT | POUND | TILDE)
     }
 
     fn peek_power(&self) -> bool {
@@ -983,10 +1311,7 @@ impl Parser {
             return false;
         }
 
-        match self.peek().tok_type {
-            POW => true,
-            _ => false,
-        }
+        matches!(self.peek().tok_type, POW)
     }
 
     fn look_ahead(&self) -> Option<TokenType> {
@@ -1000,17 +1325,22 @@ impl Parser {
 
 pub struct ParseError {
     line: usize,
+    col: usize,
     message: String,
 }
 
 impl ParseError {
-    pub fn new(line: usize, message: String) -> Self {
-        ParseError { line, message }
+    pub fn new(line: usize, col: usize, message: String) -> Self {
+        ParseError { line, col, message }
     }
 }
 
 impl RuaError for ParseError {
-    fn report(&self, filename: &str) {
-        eprintln!("rua: {}:{}: {}", filename, self.line, self.message);
+    fn message(&self, filename: &str) -> String {
+        format!("{}:{}:{}: {}", filename, self.line, self.col, self.message)
+    }
+
+    fn incomplete(&self) -> bool {
+        self.message.contains("'<eof>'")
     }
 }
\ No newline at end of file
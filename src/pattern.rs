@@ -0,0 +1,401 @@
+//! lua patterns, used by string.gsub and string.gmatch
+//!
+//! the matcher follows the one of the reference implementation (lstrlib.c),
+//! it works on the bytes of the subject and of the pattern
+
+const ESCAPE: u8 = b'%';
+const MAX_CAPTURES: usize = 32;
+// the matcher is recursive, deeper patterns are rejected instead of overflowing the stack
+const MAX_DEPTH: usize = 200;
+
+#[derive(Clone, Copy)]
+enum CaptureLen {
+    Unfinished,
+    // '()', captures the current position
+    Position,
+    Len(usize),
+}
+
+/// a captured value of a successful match
+pub enum Capture<'a> {
+    Str(&'a [u8]),
+    // 1 based, as positions are in lua
+    Position(usize),
+}
+
+/// a successful match, src[start..end] is the matched part
+pub struct Match<'a> {
+    pub start: usize,
+    pub end: usize,
+    /// the captures of the pattern, or the whole match if it has none
+    pub captures: Vec<Capture<'a>>,
+}
+
+struct MatchState<'a> {
+    src: &'a [u8],
+    pat: &'a [u8],
+    depth: usize,
+    captures: Vec<(usize, CaptureLen)>,
+}
+
+/// try to match `pat` against `src` exactly at position `start`
+///
+/// returns an error message if the pattern is malformed
+pub fn match_at<'a>(src: &'a [u8], pat: &'a [u8], start: usize) -> Result<Option<Match<'a>>, String> {
+    let mut ms = MatchState {
+        src,
+        pat,
+        depth: 0,
+        captures: Vec::new(),
+    };
+
+    let end = match ms.do_match(start, 0)? {
+        Some(end) => end,
+        None => return Ok(None),
+    };
+
+    let captures = if ms.captures.is_empty() {
+        vec![Capture::Str(&src[start..end])]
+    } else {
+        let mut captures = Vec::new();
+        for (start, len) in ms.captures {
+            captures.push(match len {
+                CaptureLen::Unfinished => return Err("unfinished capture".to_string()),
+                CaptureLen::Position => Capture::Position(start + 1),
+                CaptureLen::Len(len) => Capture::Str(&src[start..start + len]),
+            });
+        }
+        captures
+    };
+
+    Ok(Some(Match {
+        start,
+        end,
+        captures,
+    }))
+}
+
+/// does the character class `cl` (the letter after a '%') contain c
+fn match_class(c: u8, cl: u8) -> bool {
+    let res = match cl.to_ascii_lowercase() {
+        b'a' => c.is_ascii_alphabetic(),
+        b'c' => c.is_ascii_control(),
+        b'd' => c.is_ascii_digit(),
+        b'g' => c.is_ascii_graphic(),
+        b'l' => c.is_ascii_lowercase(),
+        b'p' => c.is_ascii_punctuation(),
+        // as isspace in C, which also takes '\v'
+        b's' => matches!(c, b' ' | b'\t'..=b'\r'),
+        b'u' => c.is_ascii_uppercase(),
+        b'w' => c.is_ascii_alphanumeric(),
+        b'x' => c.is_ascii_hexdigit(),
+        // an escaped non-letter, e.g. '%.', stands for itself
+        _ => return cl == c,
+    };
+
+    // upper case classes are complements, e.g. '%D' is every non digit
+    if cl.is_ascii_uppercase() {
+        !res
+    } else {
+        res
+    }
+}
+
+impl MatchState<'_> {
+    /// returns the end of the match of pat[p..] against src[s..]
+    fn do_match(&mut self, s: usize, p: usize) -> Result<Option<usize>, String> {
+        self.depth += 1;
+        if self.depth > MAX_DEPTH {
+            return Err("pattern too complex".to_string());
+        }
+        let res = self.match_items(s, p);
+        self.depth -= 1;
+
+        res
+    }
+
+    fn match_items(&mut self, mut s: usize, mut p: usize) -> Result<Option<usize>, String> {
+        loop {
+            if p == self.pat.len() {
+                return Ok(Some(s));
+            }
+
+            match self.pat[p] {
+                b'(' => {
+                    return if self.pat.get(p + 1) == Some(&b')') {
+                        self.start_capture(s, p + 2, CaptureLen::Position)
+                    } else {
+                        self.start_capture(s, p + 1, CaptureLen::Unfinished)
+                    }
+                }
+                b')' => return self.end_capture(s, p + 1),
+                // '$' anchors the match only at the end of the pattern
+                b'$' if p + 1 == self.pat.len() => {
+                    return Ok(if s == self.src.len() { Some(s) } else { None })
+                }
+                ESCAPE => match self.pat.get(p + 1) {
+                    Some(b'b') => match self.match_balance(s, p + 2)? {
+                        Some(end) => {
+                            s = end;
+                            p += 4;
+                            continue;
+                        }
+                        None => return Ok(None),
+                    },
+                    Some(b'f') => {
+                        // frontier, matches where the previous byte is not in the set
+                        // and the current one is, the edges of the subject count as '\0'
+                        p += 2;
+                        if self.pat.get(p) != Some(&b'[') {
+                            return Err("missing '[' after '%f' in pattern".to_string());
+                        }
+                        let ep = self.class_end(p)?;
+                        let previous = if s == 0 { 0 } else { self.src[s - 1] };
+                        let current = self.src.get(s).copied().unwrap_or(0);
+                        if !self.match_bracket_class(previous, p, ep - 1)
+                            && self.match_bracket_class(current, p, ep - 1)
+                        {
+                            p = ep;
+                            continue;
+                        }
+                        return Ok(None);
+                    }
+                    Some(d) if d.is_ascii_digit() => match self.match_capture(s, *d)? {
+                        Some(end) => {
+                            s = end;
+                            p += 2;
+                            continue;
+                        }
+                        None => return Ok(None),
+                    },
+                    _ => {}
+                },
+                _ => {}
+            }
+
+            // a single character class, maybe followed by a quantifier
+            let ep = self.class_end(p)?;
+            let quantifier = self.pat.get(ep).copied();
+            if !self.single_match(s, p, ep) {
+                if let Some(b'*' | b'?' | b'-') = quantifier {
+                    // zero repetitions are accepted
+                    p = ep + 1;
+                    continue;
+                }
+                return Ok(None);
+            }
+
+            match quantifier {
+                Some(b'?') => {
+                    if let Some(end) = self.do_match(s + 1, ep + 1)? {
+                        return Ok(Some(end));
+                    }
+                    p = ep + 1;
+                }
+                Some(b'+') => return self.max_expand(s + 1, p, ep),
+                Some(b'*') => return self.max_expand(s, p, ep),
+                Some(b'-') => return self.min_expand(s, p, ep),
+                _ => {
+                    s += 1;
+                    p = ep;
+                }
+            }
+        }
+    }
+
+    /// returns the index right after the character class starting at p
+    fn class_end(&self, mut p: usize) -> Result<usize, String> {
+        let c = self.pat[p];
+        p += 1;
+
+        if c == ESCAPE {
+            if p >= self.pat.len() {
+                return Err("malformed pattern (ends with '%')".to_string());
+            }
+            return Ok(p + 1);
+        }
+
+        if c == b'[' {
+            if self.pat.get(p) == Some(&b'^') {
+                p += 1;
+            }
+            // look for the closing ']', the first character of the set may be a ']'
+            loop {
+                if p >= self.pat.len() {
+                    return Err("malformed pattern (missing ']')".to_string());
+                }
+                let c = self.pat[p];
+                p += 1;
+                // skip escapes, e.g. '%]'
+                if c == ESCAPE && p < self.pat.len() {
+                    p += 1;
+                }
+                if self.pat.get(p) == Some(&b']') {
+                    break;
+                }
+            }
+            return Ok(p + 1);
+        }
+
+        Ok(p)
+    }
+
+    /// does src[s] match the character class pat[p..ep]
+    fn single_match(&self, s: usize, p: usize, ep: usize) -> bool {
+        let c = match self.src.get(s) {
+            Some(c) => *c,
+            None => return false,
+        };
+
+        match self.pat[p] {
+            b'.' => true,
+            ESCAPE => match_class(c, self.pat[p + 1]),
+            b'[' => self.match_bracket_class(c, p, ep - 1),
+            pc => pc == c,
+        }
+    }
+
+    /// does the set pat[p..=ec], from '[' to ']', contain c
+    fn match_bracket_class(&self, c: u8, mut p: usize, ec: usize) -> bool {
+        let mut sig = true;
+        p += 1;
+        if self.pat[p] == b'^' {
+            // a complemented set
+            sig = false;
+            p += 1;
+        }
+
+        while p < ec {
+            if self.pat[p] == ESCAPE {
+                p += 1;
+                if match_class(c, self.pat[p]) {
+                    return sig;
+                }
+                p += 1;
+            } else if self.pat[p + 1] == b'-' && p + 2 < ec {
+                // a range, e.g. 'a-z'
+                if self.pat[p] <= c && c <= self.pat[p + 2] {
+                    return sig;
+                }
+                p += 3;
+            } else {
+                if self.pat[p] == c {
+                    return sig;
+                }
+                p += 1;
+            }
+        }
+
+        !sig
+    }
+
+    /// '*' and '+', match as many repetitions as possible, then backtrack
+    fn max_expand(&mut self, s: usize, p: usize, ep: usize) -> Result<Option<usize>, String> {
+        let mut i = 0;
+        while self.single_match(s + i, p, ep) {
+            i += 1;
+        }
+
+        loop {
+            if let Some(end) = self.do_match(s + i, ep + 1)? {
+                return Ok(Some(end));
+            }
+            if i == 0 {
+                return Ok(None);
+            }
+            i -= 1;
+        }
+    }
+
+    /// '-', match as few repetitions as possible
+    fn min_expand(&mut self, mut s: usize, p: usize, ep: usize) -> Result<Option<usize>, String> {
+        loop {
+            if let Some(end) = self.do_match(s, ep + 1)? {
+                return Ok(Some(end));
+            }
+            if self.single_match(s, p, ep) {
+                s += 1;
+            } else {
+                return Ok(None);
+            }
+        }
+    }
+
+    fn start_capture(&mut self, s: usize, p: usize, len: CaptureLen) -> Result<Option<usize>, String> {
+        if self.captures.len() >= MAX_CAPTURES {
+            return Err("too many captures".to_string());
+        }
+
+        self.captures.push((s, len));
+        let res = self.do_match(s, p)?;
+        if res.is_none() {
+            // undo the capture
+            self.captures.pop();
+        }
+
+        Ok(res)
+    }
+
+    fn end_capture(&mut self, s: usize, p: usize) -> Result<Option<usize>, String> {
+        // close the innermost open capture
+        let l = match self
+            .captures
+            .iter()
+            .rposition(|(_, len)| matches!(len, CaptureLen::Unfinished))
+        {
+            Some(l) => l,
+            None => return Err("invalid pattern capture".to_string()),
+        };
+
+        self.captures[l].1 = CaptureLen::Len(s - self.captures[l].0);
+        let res = self.do_match(s, p)?;
+        if res.is_none() {
+            // undo the capture
+            self.captures[l].1 = CaptureLen::Unfinished;
+        }
+
+        Ok(res)
+    }
+
+    /// '%bxy', a balanced string from x to y, e.g. '%b()'
+    fn match_balance(&self, s: usize, p: usize) -> Result<Option<usize>, String> {
+        if p + 1 >= self.pat.len() {
+            return Err("malformed pattern (missing arguments to '%b')".to_string());
+        }
+        let (open, close) = (self.pat[p], self.pat[p + 1]);
+        if self.src.get(s) != Some(&open) {
+            return Ok(None);
+        }
+
+        let mut depth = 1;
+        for (i, c) in self.src.iter().enumerate().skip(s + 1) {
+            if *c == close {
+                depth -= 1;
+                if depth == 0 {
+                    return Ok(Some(i + 1));
+                }
+            } else if *c == open {
+                depth += 1;
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// '%1' to '%9', the same bytes as an earlier capture
+    fn match_capture(&self, s: usize, d: u8) -> Result<Option<usize>, String> {
+        let l = (d as usize).wrapping_sub(b'1' as usize);
+        let (start, len) = match self.captures.get(l) {
+            Some((start, CaptureLen::Len(len))) => (*start, *len),
+            // a position capture never matches
+            Some((_, CaptureLen::Position)) => return Ok(None),
+            _ => return Err(format!("invalid capture index %{} in pattern", d as char)),
+        };
+
+        if self.src.len() - s >= len && self.src[s..s + len] == self.src[start..start + len] {
+            Ok(Some(s + len))
+        } else {
+            Ok(None)
+        }
+    }
+}
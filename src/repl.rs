@@ -0,0 +1,84 @@
+use std::io::{self, BufRead, IsTerminal, Write};
+
+use crate::{
+    ast::Block,
+    builtin,
+    interpreter::Interpreter,
+    rua::{self, Rua, RuaError},
+};
+
+/// the interactive mode, reading statements from stdin until it ends
+///
+/// the input is run as soon as it is a complete chunk, an input that is an
+/// expression list has its values printed. the globals are kept from one chunk
+/// to the next, and an error only ends the chunk it happens in
+pub fn run() {
+    let mut interpreter = Interpreter::new("stdin");
+    // the prompts are only shown to someone typing
+    let interactive = io::stdin().is_terminal();
+    let mut input = String::new();
+
+    loop {
+        if interactive {
+            print!("{}", if input.is_empty() { "> " } else { ">> " });
+            io::stdout().flush().unwrap();
+        }
+        let mut line = String::new();
+        match io::stdin().lock().read_line(&mut line) {
+            Ok(0) | Err(_) => {
+                // the input ends in the middle of a chunk, which is reported as it stands
+                if !input.is_empty() {
+                    if let Err(e) = compile(&input) {
+                        e.report("stdin");
+                    }
+                }
+                break;
+            }
+            Ok(_) => input.push_str(&line),
+        }
+
+        let (block, is_expression) = match compile(&input) {
+            Ok(compiled) => compiled,
+            // wait for the rest of the chunk
+            Err(e) if e.incomplete() => continue,
+            Err(e) => {
+                e.report("stdin");
+                input.clear();
+                continue;
+            }
+        };
+        input.clear();
+
+        // a chunk that is not an expression list may end with a return,
+        // whose values are dropped
+        let res = match interpreter.exec_chunk(&block) {
+            Ok(values) if is_expression && !values.is_empty() => {
+                builtin::print(&mut interpreter, values, 1).map(|_| ())
+            }
+            res => res.map(|_| ()),
+        };
+        if let Err(e) = rua::uncaught(&mut interpreter, res) {
+            e.report("stdin");
+            interpreter.reset_stack();
+        }
+    }
+
+    if interactive {
+        println!();
+    }
+}
+
+/// parse the input as an expression list to print, as in `return <input>`,
+/// or else as a chunk; returns the block and whether it is an expression list
+fn compile(input: &str) -> Result<(Block, bool), Box<dyn RuaError>> {
+    if let Ok(block) = parse(format!("return {}", input)) {
+        return Ok((block, true));
+    }
+    Ok((parse(input.to_string())?, false))
+}
+
+fn parse(source: String) -> Result<Block, Box<dyn RuaError>> {
+    let mut program = Rua::new(source.into_bytes());
+    let tokens = program.lex().map_err(|e| Box::new(e) as Box<dyn RuaError>)?;
+    program.parse(tokens).map_err(|e| Box::new(e) as Box<dyn RuaError>)
+}
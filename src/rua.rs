@@ -1,22 +1,90 @@
+use std::{
+    fs,
+    io::{self, BufRead, Write},
+};
+
 use crate::{
     ast::Block,
+    builtin,
     interpreter::{Interpreter, RuntimeException},
     lexer::{LexError, Lexer},
     parser::{ParseError, Parser},
     token::Token,
+    value::{HostFn, Value},
 };
 
 pub struct Rua {
     pub source: Vec<u8>,
+    // the functions the host makes global before the program runs
+    natives: Vec<(String, Box<HostFn>)>,
+    // where the program prints, stdout if the host sets none
+    output: Option<Box<dyn Write>>,
+    // where the program reads input, stdin if the host sets none
+    input: Option<Box<dyn BufRead>>,
 }
 
 impl Rua {
     pub fn new(source: Vec<u8>) -> Self {
-        Self { source }
+        // replace \r\n with \n
+        let source = source
+            .iter()
+            .enumerate()
+            .filter(|(i, c)| !(**c == b'\r' && source.get(i + 1) == Some(&b'\n')))
+            .map(|(_, c)| *c)
+            .collect();
+        Self {
+            source,
+            natives: Vec::new(),
+            output: None,
+            input: None,
+        }
+    }
+
+    /// make `func` the global function `name` for the program, it gets the evaluated
+    /// arguments of each call and the line of the call. it may be a closure capturing
+    /// the host's state
+    pub fn register_global(
+        &mut self,
+        name: &str,
+        func: impl FnMut(&mut Interpreter, Vec<Value>, usize) -> Result<Value, RuntimeException> + 'static,
+    ) -> &mut Self {
+        self.natives.push((name.to_string(), Box::new(func)));
+        self
+    }
+
+    /// let the program print to `output` instead of stdout
+    pub fn set_output(&mut self, output: Box<dyn Write>) -> &mut Self {
+        self.output = Some(output);
+        self
+    }
+
+    /// let the program read its input from `input` instead of stdin
+    pub fn set_input(&mut self, input: Box<dyn BufRead>) -> &mut Self {
+        self.input = Some(input);
+        self
+    }
+
+    /// run a chunk given as text, returning the values of its top-level return statement
+    pub fn run_str(source: &str) -> Result<Vec<Value>, Box<dyn RuaError>> {
+        Self::new(source.as_bytes().to_vec()).run("string")
+    }
+
+    /// run the script at `path`, returning the values of its top-level return statement
+    pub fn run_file(path: &str) -> Result<Vec<Value>, Box<dyn RuaError>> {
+        let source = fs::read(path).map_err(|e| Box::new(e) as Box<dyn RuaError>)?;
+        Self::new(source).run(path)
+    }
+
+    /// lex, parse and interpret the program, `chunkname` is the name its errors are reported with
+    pub fn run(&mut self, chunkname: &str) -> Result<Vec<Value>, Box<dyn RuaError>> {
+        let tokens = self.lex().map_err(|e| Box::new(e) as Box<dyn RuaError>)?;
+        let block = self.parse(tokens).map_err(|e| Box::new(e) as Box<dyn RuaError>)?;
+        self.interpret(block, chunkname)
+            .map_err(|e| Box::new(e) as Box<dyn RuaError>)
     }
 
     pub fn lex(&mut self) -> Result<Vec<Token>, LexError> {
-        let mut lexer = Lexer::new(&mut self.source);
+        let mut lexer = Lexer::new(&self.source);
 
         lexer.lex()
     }
@@ -26,13 +94,211 @@ impl Rua {
         parser.parse()
     }
 
-    pub fn interpret(&self, block: Block) -> Result<(), RuntimeException> {
-        let mut interpreter = Interpreter::new();
+    /// run the program, `chunkname` is the name its errors are reported with
+    pub fn interpret(
+        &mut self,
+        block: Block,
+        chunkname: &str,
+    ) -> Result<Vec<Value>, RuntimeException> {
+        let mut interpreter = Interpreter::new(chunkname);
+        for (name, func) in self.natives.drain(..) {
+            interpreter.register_global(&name, func);
+        }
+        if let Some(output) = self.output.take() {
+            interpreter.set_output(output);
+        }
+        if let Some(input) = self.input.take() {
+            interpreter.set_input(input);
+        }
 
-        interpreter.exec_block(&block)
+        let res = interpreter.exec_chunk(&block);
+        uncaught(&mut interpreter, res)
+    }
+}
+
+/// prepare the result of running a program for reporting an uncaught error
+pub fn uncaught<T>(
+    interpreter: &mut Interpreter,
+    res: Result<T, RuntimeException>,
+) -> Result<T, RuntimeException> {
+    match res {
+        // only strings and numbers have a message to show,
+        // other error objects may have one through their __tostring metamethod
+        Err(RuntimeException::RuntimeError {
+            line,
+            value,
+            traceback,
+            ..
+        }) if !matches!(
+            value,
+            Value::Str { value: _ } | Value::Int { value: _ } | Value::Num { value: _ }
+        ) =>
+        {
+            let message = if matches!(interpreter.metafield(&value, "__tostring"), Value::Nil) {
+                None
+            } else {
+                builtin::to_string(interpreter, &value, line.unwrap_or(0)).ok()
+            };
+            let message = match message {
+                Some(message) => message,
+                None => format!("(error object is a {} value)", interpreter.type_of(&value))
+                    .into_bytes(),
+            };
+
+            Err(RuntimeException::RuntimeError {
+                line: None,
+                col: None,
+                chunkname: None,
+                value: Value::Str { value: message },
+                traceback,
+            })
+        }
+        res => res,
     }
 }
 
 pub trait RuaError {
-    fn report(&self, filename: &str);
+    /// the error message, starting with where the error is in the chunk `filename`
+    fn message(&self, filename: &str) -> String;
+
+    fn report(&self, filename: &str) {
+        eprintln!("rua: {}", self.message(filename));
+    }
+
+    /// whether the error is only that the source ends too early, like in an unfinished
+    /// `function ... end`, so that more input may complete it
+    fn incomplete(&self) -> bool {
+        false
+    }
+}
+
+impl RuaError for io::Error {
+    fn message(&self, filename: &str) -> String {
+        format!("cannot open {}: {}", filename, self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, rc::Rc};
+
+    use super::*;
+
+    /// lex and parse `source`, reporting the error if it fails
+    fn parse(source: &str) -> Block {
+        let mut rua = Rua::new(source.as_bytes().to_vec());
+        let tokens = rua.lex().expect("the source should lex");
+        match rua.parse(tokens) {
+            Ok(block) => block,
+            Err(e) => {
+                e.report("test");
+                panic!("the source should parse");
+            }
+        }
+    }
+
+    /// the values a run returned, as text, reporting the error if it failed
+    fn returned(res: Result<Vec<Value>, Box<dyn RuaError>>) -> Vec<String> {
+        match res {
+            Ok(values) => values.iter().map(Value::to_string).collect(),
+            Err(e) => {
+                e.report("string");
+                panic!("the chunk should run");
+            }
+        }
+    }
+
+    #[test]
+    fn print_handler_gets_the_printed_values() {
+        let printed = Rc::new(RefCell::new(Vec::new()));
+        let sink = Rc::clone(&printed);
+        let mut interpreter = Interpreter::new("test");
+        interpreter.set_print_handler(Box::new(move |values: &[Value]| {
+            sink.borrow_mut().extend(values.iter().map(Value::to_string));
+        }));
+
+        let block = parse("print(1, 'two') print(3.5, nil)");
+        assert!(interpreter.exec_chunk(&block).is_ok());
+        assert_eq!(*printed.borrow(), ["1", "two", "3.5", "nil"]);
+    }
+
+    #[test]
+    fn run_str_returns_the_values_of_the_chunk() {
+        assert_eq!(returned(Rua::run_str("return 1+1")), ["2"]);
+        assert_eq!(returned(Rua::run_str("return 'a', 1.5, nil")), ["a", "1.5", "nil"]);
+        assert!(returned(Rua::run_str("local x = 1")).is_empty());
+        assert!(Rua::run_str("error('boom')").is_err());
+    }
+
+    #[test]
+    fn caught_error_has_the_position_of_the_reported_one() {
+        let caught = returned(Rua::run_str("local ok, e = pcall(function() error('x') end) return e"));
+        let uncaught = match Rua::run_str("error('x')") {
+            Err(e) => e.message("string"),
+            Ok(_) => panic!("the chunk should fail"),
+        };
+        assert_eq!(caught, ["string:1: x"]);
+        assert_eq!(uncaught, caught[0]);
+    }
+
+    /// sum(...), the sum of its integer arguments
+    fn sum(_: &mut Interpreter, arguments: Vec<Value>, _: usize) -> Result<Value, RuntimeException> {
+        let value = arguments.iter().filter_map(Value::integer).sum();
+        Ok(Value::Int { value })
+    }
+
+    #[test]
+    fn registered_function_is_a_global() {
+        let mut rua = Rua::new(b"return sum(1, 2, 3), type(sum)".to_vec());
+        rua.register_global("sum", sum);
+        assert_eq!(returned(rua.run("test")), ["6", "function"]);
+    }
+
+    #[test]
+    fn registered_closure_changes_the_host_state() {
+        let log = Rc::new(RefCell::new(Vec::new()));
+        let calls = Rc::new(RefCell::new(0));
+        let (sink, counter) = (Rc::clone(&log), Rc::clone(&calls));
+        let mut rua = Rua::new(b"host_log('start') for i = 1, 2 do host_log(i) end return type(host_log)".to_vec());
+        rua.register_global("host_log", move |_, arguments, _| {
+            *counter.borrow_mut() += 1;
+            sink.borrow_mut().extend(arguments.iter().map(Value::to_string));
+            Ok(Value::Int { value: *counter.borrow() })
+        });
+        assert_eq!(returned(rua.run("test")), ["function"]);
+        assert_eq!(*calls.borrow(), 3);
+        assert_eq!(*log.borrow(), ["start", "1", "2"]);
+    }
+
+    /// a buffer the test keeps reading while the program writes to it
+    struct SharedBuffer(Rc<RefCell<Vec<u8>>>);
+
+    impl Write for SharedBuffer {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.borrow_mut().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn print_writes_to_the_output_set() {
+        let buffer = Rc::new(RefCell::new(Vec::new()));
+        let mut rua = Rua::new(b"print('hi') print(1, 2) io.write('x')".to_vec());
+        rua.set_output(Box::new(SharedBuffer(Rc::clone(&buffer))));
+        returned(rua.run("test"));
+        assert_eq!(*buffer.borrow(), b"hi\n1\t2\nx");
+    }
+
+    #[test]
+    fn print_and_io_write_keep_the_program_order() {
+        let buffer = Rc::new(RefCell::new(Vec::new()));
+        let source = "io.write('a') print('b') io.write('c', 1) print() io.write('d')";
+        let mut rua = Rua::new(source.as_bytes().to_vec());
+        rua.set_output(Box::new(SharedBuffer(Rc::clone(&buffer))));
+        returned(rua.run("test"));
+        assert_eq!(*buffer.borrow(), b"ab\nc1\nd");
+    }
 }
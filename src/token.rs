@@ -4,11 +4,17 @@ use std::fmt;
 pub struct Token {
     pub tok_type: TokenType,
     pub line: usize,
+    // the column the token starts at
+    pub col: usize,
 }
 
 impl Token {
-    pub fn new(line: usize, tok_type: TokenType) -> Self {
-        Self { line, tok_type }
+    pub fn new(line: usize, col: usize, tok_type: TokenType) -> Self {
+        Self {
+            line,
+            col,
+            tok_type,
+        }
     }
 }
 
@@ -28,6 +34,8 @@ pub enum TokenType {
     ELSE,
     ELSEIF,
     WHILE,
+    REPEAT,
+    UNTIL,
     FOR,
     DO,
     END,
@@ -50,7 +58,9 @@ pub enum TokenType {
     RIGHTBRACE,
     COMMA,
     SEMICOLON,
+    COLON,
     DOT,
+    DOTDOTDOT,
 
     // arith
     PLUS,
@@ -63,6 +73,13 @@ pub enum TokenType {
     DOTDOT,
     POUND,
 
+    // bitwise
+    TILDE, // binary xor or unary not
+    AMP,
+    PIPE,
+    SHL, // <<
+    SHR, // >>
+
     EQUAL,
     EQUALEQUAL,
     NOTEQUAL, // ~=
@@ -72,9 +89,12 @@ pub enum TokenType {
     LESSEQUAL,
 
     // types
+    // numerals with a fractional part are floats, the others integers
+    INTEGER { value: i64 },
     NUMBER { value: f64 },
     NAME { value: String },
-    STRING { value: String },
+    // the bytes of the string, which escapes may make invalid UTF-8
+    STRING { value: Vec<u8> },
 
     EOF,
 }
@@ -90,6 +110,8 @@ impl fmt::Display for TokenType {
             ELSE => write!(f, "else"),
             ELSEIF => write!(f, "elseif"),
             WHILE => write!(f, "while"),
+            REPEAT => write!(f, "repeat"),
+            UNTIL => write!(f, "until"),
             FOR => write!(f, "for"),
             DO => write!(f, "do"),
             END => write!(f, "end"),
@@ -111,7 +133,9 @@ impl fmt::Display for TokenType {
             RIGHTBRACE => write!(f, "}}"),
             COMMA => write!(f, ","),
             SEMICOLON => write!(f, ";"),
+            COLON => write!(f, ":"),
             DOT => write!(f, "."),
+            DOTDOTDOT => write!(f, "..."),
 
             PLUS => write!(f, "+"),
             MINUS => write!(f, "-"),
@@ -123,6 +147,12 @@ impl fmt::Display for TokenType {
             DOTDOT => write!(f, ".."),
             POUND => write!(f, "#"),
 
+            TILDE => write!(f, "~"),
+            AMP => write!(f, "&"),
+            PIPE => write!(f, "|"),
+            SHL => write!(f, "<<"),
+            SHR => write!(f, ">>"),
+
             EQUAL => write!(f, "="),
             EQUALEQUAL => write!(f, "=="),
             NOTEQUAL => write!(f, "~="),
@@ -131,9 +161,10 @@ impl fmt::Display for TokenType {
             GREATEREQUAL => write!(f, ">="),
             LESSEQUAL => write!(f, "<="),
 
+            INTEGER { value } => write!(f, "{}", *value),
             NUMBER { value } => write!(f, "{}", *value),
             NAME { value } => write!(f, "{}", value),
-            STRING { value } => write!(f, "\"{}\"", value),
+            STRING { value } => write!(f, "\"{}\"", String::from_utf8_lossy(value)),
 
             EOF => write!(f, "<eof>"),
         }
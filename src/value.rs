@@ -1,16 +1,37 @@
-use std::{collections::HashMap, fmt};
+use std::{cell::RefCell, collections::HashMap, fmt, rc::Rc};
 
 use ordered_float::OrderedFloat;
 
 use crate::{
     ast::{Block, NameList},
     environment::{Address, Environment},
+    interpreter::{Interpreter, RuntimeException},
 };
 
+/// a function implemented in rust, called with the evaluated arguments and the line of the call
+pub type NativeFn = fn(&mut Interpreter, Vec<Value>, usize) -> Result<Value, RuntimeException>;
+
+/// a function the host registers, which may capture and change the host's state,
+/// called like a `NativeFn`
+pub type HostFn = dyn FnMut(&mut Interpreter, Vec<Value>, usize) -> Result<Value, RuntimeException>;
+
+/// a host callback replacing print(), it gets print's arguments
+pub type PrintHandler = dyn FnMut(&[Value]);
+
+/// a native function with state, it also gets the upvalues of its closure,
+/// which it may update between calls
+pub type NativeClosureFn =
+    fn(&mut Interpreter, &mut [Value], Vec<Value>, usize) -> Result<Value, RuntimeException>;
+
+// builtins are registered once, so every copy of a NativeFunction holds the same pointer
+#[allow(unpredictable_function_pointer_comparisons)]
 #[derive(Clone, PartialEq, Hash)]
 pub enum Value {
     Bool { b: bool },
-    Str { value: String },
+    // strings are bytes, not necessarily UTF-8, as escapes like "\xff" make any byte
+    Str { value: Vec<u8> },
+    // numbers have two subtypes, integers and floats, see `Value::to_number`
+    Int { value: i64 },
     Num { value: OrderedFloat<f64> },
     Nil,
 
@@ -20,7 +41,7 @@ pub enum Value {
     ValueList { values: Vec<Value> },
 
     // Builtin Functions
-    Print,
+    NativeFunction { name: String, func: NativeFn },
 }
 
 impl Eq for Value {}
@@ -41,46 +62,92 @@ impl Value {
             // err: attempt to add(or sth) 'xxx' with 'xxx'
             Self::Bool { b: _ } => String::from("boolean"),
             Self::Str { value: _ } => String::from("string"),
-            Self::Num { value: _ } => String::from("number"),
+            Self::Int { value: _ } | Self::Num { value: _ } => String::from("number"),
 
             // err: attempt to perform on a xxx value
             Self::Nil => String::from("nil"),
             Self::Address { addr: _ } => String::from("address"),
             Self::ValueList { values: _ } => String::from("valuelist"),
-            Self::Print => String::from("function"),
+            Self::NativeFunction { name: _, func: _ } => String::from("function"),
         }
     }
 
-    /// try to convert itself to a number value
+    /// try to convert itself to a number value, either a `Value::Int` or a `Value::Num`
+    ///
+    /// a string is converted following the syntax of number literals,
+    /// so "10" and "0xA" give integers and "10.0" a float
     ///
     /// return `None` upon fail
-    pub fn number(&self) -> Option<OrderedFloat<f64>> {
+    pub fn to_number(&self) -> Option<Value> {
         match self {
-            Self::Num { value } => Some(value.clone()),
+            Self::Int { value: _ } | Self::Num { value: _ } => Some(self.clone()),
             Self::Str { value } => {
+                // surrounding whitespace is allowed, as in "  42 "
+                let value = std::str::from_utf8(value).ok()?.trim();
+                if let Ok(value) = value.parse::<i64>() {
+                    return Some(Self::Int { value });
+                }
+                // hexadecimal integers, as in "0x1F"
+                let (negative, digits) = match value.strip_prefix('-') {
+                    Some(digits) => (true, digits),
+                    None => (false, value),
+                };
+                if let Some(hex) = digits.strip_prefix("0x").or_else(|| digits.strip_prefix("0X")) {
+                    // and hexadecimal floats, as in "0x1.8p3"
+                    if hex.contains(['.', 'p', 'P']) {
+                        let value = hex_float(hex)?;
+                        return Some(Self::Num {
+                            value: OrderedFloat(if negative { -value } else { value }),
+                        });
+                    }
+                    let value = u64::from_str_radix(hex, 16).ok()? as i64;
+                    return Some(Self::Int {
+                        value: if negative { value.wrapping_neg() } else { value },
+                    });
+                }
+
                 // since f64::parse() is too powerful,
                 // we kick off some functionality here
-                // to avoid seeing "inf" or "nan" as valid numbers
-                if value.contains("inf") || value.contains("nan") || value.contains("e") {
+                // to avoid seeing "inf" or "nan" as valid numbers, exponents as in "1e3" are fine
+                let lower = value.to_ascii_lowercase();
+                if lower.contains("inf") || lower.contains("nan") {
                     return None;
                 }
 
-                if let Ok(num) = value.parse::<OrderedFloat<f64>>() {
-                    Some(num)
-                } else {
-                    None
-                }
+                value.parse::<OrderedFloat<f64>>().ok().map(|value| Self::Num { value })
             }
             _ => None,
         }
     }
 
+    /// try to convert itself to a float
+    ///
+    /// return `None` upon fail
+    pub fn number(&self) -> Option<OrderedFloat<f64>> {
+        match self.to_number()? {
+            Self::Int { value } => Some(OrderedFloat::from(value as f64)),
+            Self::Num { value } => Some(value),
+            _ => None,
+        }
+    }
+
+    /// try to convert itself to an integer, as bitwise operators require
+    ///
+    /// return `None` if it is not a number or has no exact integer representation
+    pub fn integer(&self) -> Option<i64> {
+        match self.to_number()? {
+            Self::Int { value } => Some(value),
+            Self::Num { value } => float_to_integer(value.into_inner()),
+            _ => None,
+        }
+    }
+
     /// try to convert value to a string
     /// 
     /// return Some(s) upon success
-    pub fn string(&self) -> Option<String> {
+    pub fn string(&self) -> Option<Vec<u8>> {
         match self {
-            Self::Num { value } => Some(value.to_string()),
+            Self::Int { value: _ } | Self::Num { value: _ } => Some(self.to_string().into_bytes()),
             Self::Str { value } => Some(value.clone()),
             _ => None,
         }
@@ -116,80 +183,254 @@ impl fmt::Display for Value {
         match self {
             Self::Bool { b } => write!(f, "{}", b),
             Self::Nil => write!(f, "nil"),
-            Self::Num { value } => write!(f, "{}", value),
-            Self::Str { value } => write!(f, "{}", value),
+            Self::Int { value } => write!(f, "{}", value),
+            Self::Num { value } => write!(f, "{}", format_float(value.into_inner())),
+            // bytes that are not UTF-8 show as the replacement character
+            Self::Str { value } => write!(f, "{}", String::from_utf8_lossy(value)),
             Self::Address { addr } => write!(f, "{}", addr),
             Self::ValueList { values } => {
                 let n = values.len();
                 if n == 0 {
                     Ok(())
                 } else {
-                    for i in 0..(n - 1) {
-                        write!(f, "{}, ", values[i])?;
+                    for value in &values[..n - 1] {
+                        write!(f, "{}, ", value)?;
                     }
                     write!(f, "{}", values[n - 1])
                 }
             }
-            Self::Print => write!(f, "print"),
+            Self::NativeFunction { name, func: _ } => write!(f, "{}", name),
+        }
+    }
+}
+
+/// the length of a string is its number of bytes,
+/// shared by the '#' operator and string.len so that they always agree
+pub fn byte_len(s: &[u8]) -> i64 {
+    s.len() as i64
+}
+
+/// the value of the digits of a hexadecimal float after "0x", with an optional fractional
+/// part and binary exponent, as in "1.8p3", `None` if they are malformed
+fn hex_float(hex: &str) -> Option<f64> {
+    let (digits, exponent) = match hex.find(['p', 'P']) {
+        Some(i) => (&hex[..i], hex[i + 1..].parse::<i32>().ok()?),
+        None => (hex, 0),
+    };
+    let (int, frac) = digits.split_once('.').unwrap_or((digits, ""));
+    if int.len() + frac.len() == 0 {
+        return None;
+    }
+    let mut mantissa = 0.0;
+    for c in int.chars().chain(frac.chars()) {
+        mantissa = mantissa * 16.0 + c.to_digit(16)? as f64;
+    }
+    Some(mantissa * 2f64.powi(exponent.saturating_sub(4 * frac.len() as i32)))
+}
+
+/// the integer equal to a float, `None` if it has a fractional part or is out of range
+pub fn float_to_integer(value: f64) -> Option<i64> {
+    // i64::MAX as f64 rounds up to 2^63, which is already out of range
+    if value.fract() == 0.0 && value >= i64::MIN as f64 && value < i64::MAX as f64 {
+        Some(value as i64)
+    } else {
+        None
+    }
+}
+
+/// format a float as lua does, with 14 significant digits ("%.14g"),
+/// adding ".0" to the ones that would look like integers, e.g. `4.0`
+fn format_float(value: f64) -> String {
+    let s = format_g14(value);
+    if !value.is_finite() || s.contains(['.', 'e']) {
+        s
+    } else {
+        s + ".0"
+    }
+}
+
+/// format a float with 14 significant digits, as C's "%.14g" does
+pub fn format_g14(value: f64) -> String {
+    if value.is_nan() {
+        return String::from(if value.is_sign_negative() { "-nan" } else { "nan" });
+    }
+    if value.is_infinite() {
+        return String::from(if value < 0.0 { "-inf" } else { "inf" });
+    }
+
+    // the exponent of the value once rounded to 14 digits
+    let sci = format!("{:.13e}", value);
+    let (mantissa, exp) = sci.split_once('e').unwrap();
+    let exp: i32 = exp.parse().unwrap();
+
+    if (-4..14).contains(&exp) {
+        let fixed = format!("{:.*}", (13 - exp) as usize, value);
+        if fixed.contains('.') {
+            fixed.trim_end_matches('0').trim_end_matches('.').to_string()
+        } else {
+            fixed
+        }
+    } else {
+        let mantissa = if mantissa.contains('.') {
+            mantissa.trim_end_matches('0').trim_end_matches('.')
+        } else {
+            mantissa
+        };
+        format!("{}e{}{:02}", mantissa, if exp < 0 { '-' } else { '+' }, exp.abs())
+    }
+}
+
+/// floats with an exact integer value are stored as integers,
+/// so that `t[1]` and `t[1.0]` are the same field
+fn normalize_key(key: &Value) -> Value {
+    if let Value::Num { value } = key {
+        if let Some(value) = float_to_integer(value.into_inner()) {
+            return Value::Int { value };
         }
     }
+
+    key.clone()
 }
 
 /// the inner structure of HeapObj::Table
+///
+/// the (key, value) pairs are kept in slots, so that `next` can walk through them.
+/// assigning nil to a key keeps its slot (with a nil value) until new keys are added,
+/// then traversals can go on after the current key is removed
 #[derive(Clone)]
 pub struct Table {
-    map: HashMap<Value, Value>,
+    // key -> its slot in `entries`
+    map: HashMap<Value, usize>,
+    entries: Vec<(Value, Value)>,
+    // the number of slots with a value that is not nil
+    count: usize,
 }
 
 impl Table {
     pub fn new() -> Self {
         Self {
             map: HashMap::new(),
+            entries: Vec::new(),
+            count: 0,
         }
     }
 
     /// get the value inside table
     pub fn index(&self, i: &Value) -> Value {
-        match self.map.get(i) {
-            Some(v) => v.clone(),
+        match self.map.get(&normalize_key(i)) {
+            Some(slot) => self.entries[*slot].1.clone(),
             None => Value::Nil,
         }
     }
 
     /// update the table
     pub fn insert(&mut self, key: Value, val: Value) {
+        let key = normalize_key(&key);
+        if let Some(slot) = self.map.get(&key) {
+            let old = &mut self.entries[*slot].1;
+            match (&*old, &val) {
+                (Value::Nil, Value::Nil) => {}
+                (Value::Nil, _) => self.count += 1,
+                (_, Value::Nil) => self.count -= 1,
+                _ => {}
+            }
+            *old = val;
+            return;
+        }
+
         if let Value::Nil = val {
-            self.map.remove(&key);
-        } else {
-            self.map.insert(key, val);
+            return;
+        }
+        // a new key, drop the slots of removed keys once they outnumber the others
+        if self.entries.len() - self.count > self.count.max(8) {
+            self.entries.retain(|(_, v)| !matches!(v, Value::Nil));
+            self.map = self
+                .entries
+                .iter()
+                .enumerate()
+                .map(|(slot, (k, _))| (k.clone(), slot))
+                .collect();
         }
+        self.map.insert(key.clone(), self.entries.len());
+        self.entries.push((key, val));
+        self.count += 1;
     }
 
-    /// get table's number of (key, value) pairs
-    pub fn len(&self) -> usize {
-        self.map.len()
+    /// a border of the table, an n with t[n] not nil and t[n + 1] nil (0 if t[1] is nil),
+    /// only the integer keys count
+    ///
+    /// the table library appends and shifts elements based on it. like lua, it doubles
+    /// n until t[n] is nil, then binary searches a border below it, so in a table with holes
+    /// it may be any of its borders
+    pub fn border(&self) -> usize {
+        let present = |n: usize| !matches!(self.index(&Value::Int { value: n as i64 }), Value::Nil);
+        if !present(1) {
+            return 0;
+        }
+
+        // t[i] is not nil and t[j] is nil
+        let (mut i, mut j) = (1, 2);
+        while present(j) {
+            i = j;
+            j *= 2;
+        }
+        while j - i > 1 {
+            let m = i + (j - i) / 2;
+            if present(m) {
+                i = m;
+            } else {
+                j = m;
+            }
+        }
+
+        i
     }
-}
 
-impl IntoIterator for Table {
-    type Item = (Value, Value);
-    type IntoIter = std::collections::hash_map::IntoIter<Value, Value>;
+    /// the (key, value) pair after `key`, nil starts the traversal
+    ///
+    /// returns `Some(None)` at the end of the traversal, and `None` if key is not in the table
+    pub fn next(&self, key: &Value) -> Option<Option<(Value, Value)>> {
+        let start = match key {
+            Value::Nil => 0,
+            key => self.map.get(&normalize_key(key))? + 1,
+        };
 
-    fn into_iter(self) -> Self::IntoIter {
-        self.map.into_iter()
+        Some(
+            self.entries[start..]
+                .iter()
+                .find(|(_, v)| !matches!(v, Value::Nil))
+                .cloned(),
+        )
     }
 }
 
 /// represent functions and tables
 #[derive(Clone)]
 pub enum HeapObj {
+    // the parameters, body and closure are shared, so a call does not copy them
     Function {
-        parameters: NameList,
-        body: Block,
-        closure: Vec<Environment>,
+        parameters: Rc<NameList>,
+        body: Rc<Block>,
+        closure: Rc<[Environment]>,
+        // where the function is defined, shown by tostring
+        def_line: usize,
+        // the chunk the function is defined in, whose name its errors are reported with
+        chunkname: Rc<str>,
     },
     Table {
         table: Table,
+        // set by setmetatable, the table whose fields are the metamethods
+        metatable: Option<Address>,
+    },
+    // a builtin holding some state, e.g. the iterator of string.gmatch
+    NativeClosure {
+        func: NativeClosureFn,
+        upvalues: Vec<Value>,
+    },
+    // a rust closure of the host, see `Interpreter::register_global`
+    HostFunction {
+        name: String,
+        func: Rc<RefCell<HostFn>>,
     },
 }
 
@@ -201,8 +442,18 @@ impl HeapObj {
                 parameters: _,
                 body: _,
                 closure: _,
+                def_line: _,
+                chunkname: _,
+            } => String::from("function"),
+            Self::Table {
+                table: _,
+                metatable: _,
+            } => String::from("table"),
+            Self::NativeClosure {
+                func: _,
+                upvalues: _,
             } => String::from("function"),
-            Self::Table { table: _ } => String::from("table"),
+            Self::HostFunction { name: _, func: _ } => String::from("function"),
         }
     }
 }
@@ -0,0 +1,45 @@
+-- arithmetic on tables is passed to the metamethods of their metatables
+Vector = {}
+Vector.__index = Vector
+function Vector.new(x, y)
+    return setmetatable({x = x, y = y}, Vector)
+end
+function Vector.__add(a, b)
+    return Vector.new(a.x + b.x, a.y + b.y)
+end
+function Vector.__unm(v)
+    return Vector.new(-v.x, -v.y)
+end
+function Vector.__mul(a, b)
+    -- scaling by a number on either side
+    if type(a) == "number" then
+        return Vector.new(a * b.x, a * b.y)
+    end
+    return Vector.new(a.x * b, a.y * b)
+end
+
+v = Vector.new(1, 2) + Vector.new(3, 4)
+print(v.x, v.y) -- 4, 6
+v = -v
+print(v.x, v.y) -- -4, -6
+v = 2 * Vector.new(1, 2)
+print(v.x, v.y) -- 2, 4
+v = Vector.new(1, 2) * 3
+print(v.x, v.y) -- 3, 6
+
+-- the left operand's metamethod comes first
+A = setmetatable({}, {__sub = function() return "A" end})
+B = setmetatable({}, {__sub = function() return "B" end})
+print(A - B, B - A, A - 1, 1 - B) -- A, B, A, B
+
+-- every arithmetic operator, only the first result is used
+m = setmetatable({}, {
+    __div = function() return "div", 1 end,
+    __idiv = function() return "idiv" end,
+    __mod = function() return "mod" end,
+    __pow = function() return "pow" end,
+})
+print(m / 1, m // 1, m % 1, m ^ 1, 2 ^ m) -- div, idiv, mod, pow, pow
+
+-- without a metamethod it is still an error
+print(pcall(function() return {} + 1 end)) -- false, tests/arith_meta.lua:45: attempt to add table with number
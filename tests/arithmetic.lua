@@ -34,6 +34,13 @@ print(3975 % "1935")
 print("3975" % "1935")
 print("3975" % 1935)
 
+-- the result has the sign of the divisor, a - floor(a / b) * b
+print(5 % 3, -5 % 3) -- 2, 1
+print(5 % -3, -5 % -3) -- -1, -2
+print(5.5 % -2, -6 % 3 == 0) -- -0.5, true
+print(5 % math.huge, -5 % math.huge) -- 5.0, inf
+print(5.0 % 0 ~= 5.0 % 0) -- true
+
 -- exponation
 print(3975 ^ 2.5)
 print(3975 ^ "2.5")
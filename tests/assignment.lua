@@ -24,4 +24,21 @@ print(i, a[3], a[4])  -- 4, 20, 4
 
 x, y, z = 10, 20, 30
 x, y, z = y, z, x
-print(x, y, z) -- 20, 30, 10
\ No newline at end of file
+print(x, y, z) -- 20, 30, 10
+
+-- a local declaration without '=' assigns nil to every name
+a, b = 1, 2
+do
+    local a, b
+    print(a, b) -- nil, nil
+end
+print(a, b) -- 1, 2
+
+-- fields missing a value on the right hand side are assigned nil too
+t = {a = 0, b = 0}
+t.a, t.b = 1
+print(t.a, t.b) -- 1, nil
+function nothing() end
+t.x = 5
+t.x = nothing()
+print(t.x) -- nil
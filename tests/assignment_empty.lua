@@ -0,0 +1,4 @@
+-- an assignment needs at least one expression on its right hand side,
+-- so this is a syntax error rather than assigning nil to a and b:
+-- rua: tests/assignment_empty.lua:5:1: unexpected symbol near '<eof>'
+a, b =
@@ -0,0 +1,26 @@
+-- '~' is bitwise not when unary and bitwise xor when binary
+print(~0) -- -1
+print(~5) -- -6
+print(5 ~ 3) -- 6
+print(5 ~ 5) -- 0
+print(~~7) -- 7
+
+-- '~' binds looser than '..' and arithmetic, but tighter than comparison
+print(1 + 2 ~ 3) -- 0
+print(6 ~ 3 == 5) -- true
+print(1 ~= 2) -- true, '~=' is still inequality
+
+-- strings convertible to numbers are accepted
+print("12" ~ 10) -- 6
+
+-- and, or and the shifts work on integers too
+print(0xF0 | 0x0F == 255, 6 & 3 == 2, 1 << 4 == 16, ~0 == -1) -- true, true, true, true
+print(0xF0 | 0x0F, 6 & 3, 5 & -1, 1 << 4, 256 >> 4) -- 255, 2, 5, 16, 16
+print(-1 >> 1 == 0x7FFFFFFFFFFFFFFF, -1 >> 63, 1 << 63 == -0x7FFFFFFFFFFFFFFF - 1) -- true, 1, true
+print(1 << 64, 1 >> 64, 2 << -1, 2 >> -1) -- 0, 0, 1, 4
+print(3.0 | 0, "3" & 1) -- 3, 1
+
+-- from the loosest: '|', '~', '&', then the shifts, all looser than '..'
+print(1 | 2 ~ 3 & 4 << 1) -- 3
+print(1 << 2 + 1, 5 & 3 == 1) -- 8, true
+print((1 | 2) == 3, 1 | 2 < 4) -- true, true
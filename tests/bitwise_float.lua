@@ -0,0 +1,4 @@
+-- bitwise operators need integer operands:
+-- rua: tests/bitwise_float.lua:4:11: number has no integer representation
+print(6 & 3)
+print(1.5 | 1)
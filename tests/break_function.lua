@@ -0,0 +1,14 @@
+-- a break inside a function does not end the loop around the call:
+-- rua: tests/break_function.lua:8: <break> at line 8 not inside a loop
+-- stack traceback:
+--     tests/break_function.lua:8: in function 'f'
+--     tests/break_function.lua:13: in main chunk
+function f()
+    for i = 1, 10 do break end -- ends the loop inside f
+    break
+end
+
+for i = 1, 3 do
+    print(i) -- 1
+    f()
+end
@@ -0,0 +1,14 @@
+-- builtins are ordinary function values
+p = print
+p("alias") -- alias
+
+function apply(f, x)
+    return f(x)
+end
+print(apply(type, 1)) -- number
+
+t = {show = print, kind = type}
+t.show(t.kind(t)) -- table
+
+print(print == p, print == type) -- true, false
+print(type(p)) -- function
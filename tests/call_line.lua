@@ -0,0 +1,10 @@
+-- a call inside an expression reports the line where the call starts:
+-- rua: tests/call_line.lua:9: attempt to call a nil value (global 'g')
+function f(x)
+    return x
+end
+
+result = f(1) +
+    f(2) * (3 -
+    g(4)) +
+    f(5)
@@ -0,0 +1,33 @@
+-- a table with a __call metamethod can be called, it gets the table before the arguments
+counter = setmetatable({n = 0}, {__call = function(self, step)
+    self.n = self.n + (step or 1)
+    return self.n, "called"
+end})
+print(counter()) -- 1, called
+print(counter(10)) -- 11, called
+print(pcall(counter, 5)) -- true, 16, called
+print(pcall(function() local t = {} return t() end)) -- false, tests/call_meta.lua:9: attempt to call a table value
+
+-- __concat is used when an operand is not a string or a number
+List = {}
+List.__concat = function(a, b)
+    local s = ""
+    for _, v in ipairs(type(a) == "table" and a or {a}) do s = s .. v end
+    for _, v in ipairs(type(b) == "table" and b or {b}) do s = s .. v end
+    return s
+end
+l = setmetatable({"a", "b"}, List)
+print(l .. "c", "z" .. l, 1 .. l) -- abc, zab, 1ab
+
+-- __len is used by the length operator
+sized = setmetatable({1, 2, 3}, {__len = function(t) return 42, 1 end})
+print(#sized, #{1, 2, 3}) -- 42, 3
+
+-- __tostring is used by tostring, print and string.format
+Point = {}
+Point.__tostring = function(p) return "(" .. p.x .. ", " .. p.y .. ")" end
+p = setmetatable({x = 1, y = 2}, Point)
+print(p, tostring(p)) -- (1, 2), (1, 2)
+print(string.format("p = %s", p)) -- p = (1, 2)
+bad = setmetatable({}, {__tostring = function() return 1 end})
+print(pcall(tostring, bad)) -- false, tests/call_meta.lua:33: '__tostring' must return a string
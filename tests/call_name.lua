@@ -0,0 +1,15 @@
+-- calling a value that is not a function names it, when the call does
+t = {}
+print(pcall(function() foo() end)) -- false tests/call_name.lua:3: attempt to call a nil value (global 'foo')
+print(pcall(function() t.bar() end)) -- false tests/call_name.lua:4: attempt to call a nil value (field 'bar')
+print(pcall(function() t["baz"](1) end)) -- false tests/call_name.lua:5: attempt to call a nil value (field 'baz')
+print(pcall(function() t:qux() end)) -- false tests/call_name.lua:6: attempt to call a nil value (method 'qux')
+
+-- the type of the value is told as well
+number = 10
+print(pcall(function() number() end)) -- false tests/call_name.lua:10: attempt to call a number value (global 'number')
+print(pcall(function() t.inner = {} t.inner() end)) -- false tests/call_name.lua:11: attempt to call a table value (field 'inner')
+
+-- a local or a computed function has no name to tell
+print(pcall(function() local f f() end)) -- false tests/call_name.lua:14: attempt to call a nil value
+print(pcall(function() t[1]() end)) -- false tests/call_name.lua:15: attempt to call a nil value
@@ -0,0 +1,70 @@
+-- closures share the variables they capture with the scope they are defined in
+function make()
+    local n = 0
+    return function()
+        n = n + 1
+        return n
+    end
+end
+c1 = make()
+print(c1(), c1()) -- 1, 2
+c2 = make()
+print(c2(), c1(), c2()) -- 1, 3, 2
+
+-- two closures of the same scope share its variables
+function account()
+    local balance = 0
+    local function deposit(v) balance = balance + v end
+    local function get() return balance end
+    return deposit, get
+end
+deposit, get = account()
+deposit(10)
+deposit(5)
+print(get()) -- 15
+
+-- the defining scope sees the changes made by the closure, and the other way round
+local x = 1
+local function bump() x = x + 1 end
+bump()
+print(x) -- 2
+x = 10
+bump()
+print(x) -- 11
+
+-- every iteration of a loop has its own variables
+fs = {}
+for i = 1, 3 do
+    fs[i] = function() return i end
+end
+print(fs[1](), fs[2](), fs[3]()) -- 1, 2, 3
+
+-- a local declared after the closure is a different variable
+y = "global"
+local function show() return y end
+local y = "local"
+print(show(), y) -- global, local
+
+-- forward declared locals allow mutual recursion
+local isEven, isOdd
+function isEven(n)
+    if n == 0 then return true end
+    return isOdd(n - 1)
+end
+function isOdd(n)
+    if n == 0 then return false end
+    return isEven(n - 1)
+end
+print(isEven(10), isOdd(7), isEven(3)) -- true, true, false
+
+-- a function does not see the locals of its caller, only the ones it is defined among
+function setg() g = 1 end
+function getg() return g end
+local g = 0
+setg()
+print(g, getg()) -- 0, 1
+local function caller()
+    local g = "caller"
+    return getg()
+end
+print(caller()) -- 1
@@ -0,0 +1,6 @@
+-- errors before running report the column too, counted in bytes from 1:
+-- rua: tests/column.lua:6:26: unexpected symbol near '@'
+local s = [[
+skipped newline]]
+local t = "a\tb\\"
+local x = s .. t .. "\n" @ 1
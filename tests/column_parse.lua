@@ -0,0 +1,5 @@
+-- a parse error points at the token it is about:
+-- rua: tests/column_parse.lua:4:15: cannot use '...' outside a vararg function near '...'
+function f(a, b)
+  return a + (... or b)
+end
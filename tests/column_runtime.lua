@@ -0,0 +1,4 @@
+-- a runtime error of an operation is reported at the column of its operator:
+-- rua: tests/column_runtime.lua:4:17: attempt to perform arithmetic on table value
+local t = {}
+local x = 2 + 1 ^ t * 3
@@ -0,0 +1,7 @@
+-- comments do not change the line numbers of what follows them:
+-- rua: tests/comment_line.lua:7:13: attempt to add nil with number
+local a = 1 -- a comment after code
+--[[ a long
+comment ]] local b = 2
+-- the last comment
+local c = x + 1
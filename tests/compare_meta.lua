@@ -0,0 +1,30 @@
+-- tables are compared by the __eq, __lt and __le metamethods of their metatables
+Version = {}
+function Version.new(major, minor)
+    return setmetatable({major = major, minor = minor}, Version)
+end
+function Version.__eq(a, b)
+    return a.major == b.major and a.minor == b.minor
+end
+function Version.__lt(a, b)
+    return a.major < b.major or (a.major == b.major and a.minor < b.minor)
+end
+function Version.__le(a, b)
+    return not (b < a)
+end
+
+v1, v2, v3 = Version.new(1, 2), Version.new(1, 10), Version.new(1, 2)
+print(v1 < v2, v2 < v1, v1 <= v3, v2 <= v1) -- true, false, true, false
+-- a > b is b < a and a >= b is b <= a
+print(v2 > v1, v1 > v2, v1 >= v3, v1 >= v2) -- true, false, true, false
+print(v1 == v3, v1 ~= v3, v1 == v2) -- true, false, false
+
+-- __eq is only tried when both operands are tables,
+-- and its result is converted to a boolean
+t = setmetatable({}, {__eq = function() return 1 end})
+print(t == {}, t == 1, t == "t") -- true, false, false
+
+-- the right operand's metamethod is used when the left one has none
+big = setmetatable({}, {__lt = function(a, b) return b == big end})
+print({} < big, 1 < big, big < 1) -- true, true, false
+print(pcall(function() return {} < {} end)) -- false, tests/compare_meta.lua:30: attempt to compare table with table
@@ -0,0 +1,4 @@
+-- '..' is right associative, so "a" .. nil .. "b" evaluates nil .. "b" first,
+-- where nil is the bad operand:
+-- rua: tests/concat_assoc.lua:4:18: attempt to concatenate a nil value
+print("a" .. nil .. "b")
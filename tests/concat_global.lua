@@ -0,0 +1,2 @@
+-- rua: tests/concat_global.lua:2:11: attempt to concatenate a nil value (global 'y')
+print("x" .. y)
@@ -0,0 +1,4 @@
+-- the variable or field holding the bad operand is named when it is known:
+-- rua: tests/concat_name.lua:4:11: attempt to concatenate a nil value (field 'name')
+t = {}
+print("x" .. t.name)
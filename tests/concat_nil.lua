@@ -0,0 +1,3 @@
+-- the error names the operand that is not a string or a number:
+-- rua: tests/concat_nil.lua:3:11: attempt to concatenate a nil value
+print(nil .. "x")
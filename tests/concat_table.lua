@@ -0,0 +1,2 @@
+-- rua: tests/concat_table.lua:2:11: attempt to concatenate a table value
+print("x" .. {})
@@ -0,0 +1,10 @@
+-- a number can start with its decimal point
+print(.5 == 0.5, .5 + .25) -- true, 0.75
+print(.25) -- 0.25
+print(-.5, 2 * .5, .1e1) -- -0.5, 1.0, 1.0
+
+-- concatenation and field access are still lexed as before
+local a, b = "con", "cat"
+print(a..b, 1 ..2) -- concat, 12
+local t = {x = 10}
+print(t.x, #{...}) -- 10, 0
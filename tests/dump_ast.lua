@@ -0,0 +1,7 @@
+-- run with `rua --dump-ast tests/dump_ast.lua`: the program is parsed and printed, not run:
+-- local Namelist(x) = ExpList((1 + (2 * 3)))
+-- if((x > 2)) {
+-- print(ExpList(x))
+-- } 
+local x = 1 + 2 * 3
+if x > 2 then print(x) end
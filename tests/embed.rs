@@ -0,0 +1,10 @@
+// rua as a library, driven the way a host program would
+use rua::{Rua, Value};
+
+#[test]
+fn host_runs_a_chunk_and_reads_its_values() {
+    let values = Rua::run_str("local t = {1, 2, 3} return #t, 'done'")
+        .unwrap_or_else(|e| panic!("{}", e.message("string")));
+    assert!(matches!(values[0], Value::Int { value: 3 }));
+    assert_eq!(values[1].to_string(), "done");
+}
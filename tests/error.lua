@@ -0,0 +1,56 @@
+-- pcall catches an error and returns false and the error object
+ok, e = pcall(function() error("x") end)
+print(ok, e) -- false, tests/error.lua:2: x
+
+-- level 2 points at the call of the function that called error, 0 adds no position
+function check(n)
+    if type(n) ~= "number" then
+        error("number expected", 2)
+    end
+end
+print(pcall(function()
+    check("a") -- line 12
+end)) -- false, tests/error.lua:12: number expected
+print(pcall(error, "plain", 0)) -- false, plain
+
+-- the error object may be any value
+t = {}
+ok, e = pcall(error, t)
+print(ok, e == t) -- false, true
+
+-- errors of the interpreter are caught as well
+print(pcall(function() return 1 + nil end)) -- false, tests/error.lua:22: attempt to add number with nil
+print(pcall(nil)) -- false, tests/error.lua:23: attempt to call a nil value
+
+-- on success pcall returns true followed by every result
+local ok, a, b = pcall(function() return 1, 2 end)
+print(ok, a, b) -- true, 1, 2
+print(pcall(string.rep, "ab", 2)) -- true, abab
+
+-- a return inside the protected function is its normal return
+print(pcall(function()
+    for i = 1, 10 do
+        if i == 3 then return i end
+    end
+end)) -- true, 3
+
+-- a break ends the loop inside the protected function, not pcall
+print(pcall(function()
+    local n = 0
+    while true do
+        n = n + 1
+        if n == 5 then break end
+    end
+    return n
+end)) -- true, 5
+
+-- a break outside any loop of the function is an error
+print(pcall(function() break end)) -- false, tests/error.lua:48: <break> at line 48 not inside a loop
+
+-- assert returns its arguments, or raises its message as it is
+print(assert(1, "unused")) -- 1, unused
+print(pcall(assert, false, "boom")) -- false, boom
+print(pcall(assert, nil)) -- false, tests/error.lua:53: assertion failed!
+
+-- nested pcall
+print(pcall(pcall, error, "inner", 0)) -- true, false, inner
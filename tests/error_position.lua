@@ -0,0 +1,8 @@
+-- the position error() adds is "chunkname:line: ", as the top level reports it without "rua: "
+local ok, e = pcall(function() error("x") end)
+print(e) -- tests/error_position.lua:2: x
+print(select(2, string.gsub(e, "^.*:%d+: x$", ""))) -- 1
+
+-- assert's default message gets it too
+ok, e = pcall(function() assert(false) end)
+print(select(2, string.gsub(e, "^.*:%d+: assertion failed!$", ""))) -- 1
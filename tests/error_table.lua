@@ -0,0 +1,3 @@
+-- an uncaught error object that is not a string or a number is reported by its type:
+-- rua: (error object is a table value)
+error({msg = "x"})
@@ -0,0 +1,7 @@
+-- a table error object keeps its identity through pcall, and raised again
+-- at the top level it is reported by its type:
+-- rua: (error object is a table value)
+local err = {code = 1}
+local ok, e = pcall(error, err)
+print(ok, e == err, e.code) -- false, true, 1
+error(e)
@@ -0,0 +1,4 @@
+-- an uncaught error object is reported through its __tostring metamethod:
+-- rua: disk full
+err = setmetatable({}, {__tostring = function() return "disk full" end})
+error(err)
@@ -0,0 +1,11 @@
+-- an uncaught error() is reported like the errors of the interpreter:
+-- rua: tests/error_uncaught.lua:8: boom
+-- stack traceback:
+--     tests/error_uncaught.lua:8: in function 'fail'
+--     tests/error_uncaught.lua:11: in main chunk
+function fail()
+    print("before") -- before
+    error("boom")
+    print("not printed")
+end
+fail()
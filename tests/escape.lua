@@ -0,0 +1,8 @@
+-- each escape sequence becomes a single byte, shown here by its code
+print(string.format("%q", "\a\b\f\v")) -- "\7\8\12\11"
+print(string.format("%q", "\t\r\0")) -- "\9\r\0"
+print(#"\a\b\f\n\r\t\v\0", #"a\0b") -- 8, 3
+print("\\", '\'', "\"") -- \, ', "
+
+-- an escaped quote or backslash does not end the string early
+print("a\\", 'it\'s', "say \"hi\"") -- a\, it's, say "hi"
@@ -0,0 +1,9 @@
+-- a byte can be written by its decimal or hexadecimal code
+print("\65" == "A", "\x41" == "A", "\x6a" == "\x6A") -- true, true, true
+print("\0659", "\x414", "a\10b" == "a\nb") -- A9, A4, true
+print("\0" == "\x00", #"\1\02\003", "\xC3\xA9" == "\195\169") -- true, 3, true
+print(string.format("%q", "\127\x7f")) -- "\127\127"
+-- any byte can be escaped, not only those of UTF-8 text
+print(#"\xff\x41", #"\200", "\xff" == "\255") -- 2, 1, true
+print(("\xff\x41"):sub(2) == "A", "\200" < "\201", string.format("%q", "\xff") == '"\255"') -- true, true, true
+print(#string.gsub("\xff\x41", "A", "B"), string.gsub("\xff\x41", "A", "B") == "\xffB") -- 2, true
@@ -0,0 +1,3 @@
+-- a hexadecimal escape needs exactly two digits:
+-- rua: tests/escape_hex.lua:3:6: hexadecimal digit expected '\x4g'
+s = "\x4g"
@@ -0,0 +1,3 @@
+-- an unknown escape sequence is a lex error:
+-- rua: tests/escape_invalid.lua:3:7: invalid escape sequence '\q'
+s = "a\qb"
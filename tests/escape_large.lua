@@ -0,0 +1,3 @@
+-- a decimal escape must fit in a byte:
+-- rua: tests/escape_large.lua:3:6: decimal escape too large '\256'
+s = "\256"
@@ -0,0 +1,8 @@
+-- numerals with an exponent are floats
+print(1e3 == 1000, 2.5e-1 == 0.25, 1E2) -- true, true, 100.0
+print(5e+2, 1e-3, 1.5e300 * 10) -- 500.0, 0.001, 1.5e+301
+print(2e0 + 1, 1e2 // 1) -- 3.0, 100.0
+
+-- strings with an exponent are converted too, but not "inf" or "nan"
+print(tonumber("1e2") == 100, tonumber("1e2"), "2E1" + 1) -- true, 100.0, 21.0
+print(tonumber("inf"), tonumber("NaN"), tonumber("1e")) -- nil, nil, nil
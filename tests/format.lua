@@ -0,0 +1,38 @@
+print(string.format("%05.2f", 3.14159)) -- 03.14
+print(string.format("%s=%d", "n", 7)) -- n=7
+print(string.format("%d%%", 50)) -- 50%
+print(string.format("[%5d|%-5d|%+d|% d]", 42, 42, 42, 42)) -- [   42|42   |+42| 42]
+print(string.format("%.3d %i %u", 7, -3, 8)) -- 007 -3 8
+print(string.format("%x %X %#x %o", 255, 255, 255, 8)) -- ff FF 0xff 10
+print(string.format("%f %.1f %.0f", 1.5, 2.25, 2.5)) -- 1.500000 2.2 2
+print(string.format("%e %.2E", 12345.678, 0.00012)) -- 1.234568e+04 1.20E-04
+print(string.format("[%10s|%-6s|%.2s]", "right", "left", "cut")) -- [     right|left  |cu]
+print(string.format("%s %s %s", nil, true, 1.5)) -- nil true 1.5
+print(string.format("%c%c%c", 76, 117, 97)) -- Lua
+print(string.format("%5.1f|%-8.3f|", -1.25, 3)) -- -1.2|3.000   |
+
+-- %d of negative and large values
+print(string.format("%d", -5)) -- -5
+print(string.format("%d", 2^40)) -- 1099511627776
+print(string.format("%d", 2^53)) -- 9007199254740992
+-- past 2^53 a float is exact, but may not be the value written: 2^53 + 1 rounds to 2^53
+print(string.format("%d", 2^53 + 1), string.format("%d", 2^53 + 2)) -- 9007199254740992, 9007199254740994
+print(string.format("%d", 9007199254740993)) -- 9007199254740993
+print(pcall(string.format, "%d", 2^63)) -- false, tests/format.lua:21: bad argument #2 to 'format' (number has no integer representation)
+print(string.format("%d", "10")) -- 10
+print(string.format("%x", -1)) -- ffffffffffffffff
+
+-- %q writes values so that they read back
+print(string.format("%q", 'say "hi"\nback\\slash')) -- "say \"hi\"\
+-- back\\slash"
+print(string.format("%q", "tab\tx"), string.format("%q", "tab\t1")) -- "tab\9x", "tab\0091"
+print(string.format("%q", 10)) -- 10
+print(string.format("%q", 0.5), string.format("%q", -3.75)) -- 0x1p-1, -0x1.ep+1
+print(string.format("%q", 1/0), string.format("%q", -1/0)) -- 1e9999, -1e9999
+print(string.format("%q", nil), string.format("%q", false)) -- nil, false
+
+-- and load reads them back as the same values
+print(load("return " .. string.format("%q", 0.1))() == 0.1) -- true
+print(load("return " .. string.format("%q", -3.75))()) -- -3.75
+print(load("return " .. string.format("%q", 1/0))() == 1/0) -- true
+print(load("return " .. string.format("%q", nil))()) -- nil
@@ -47,4 +47,18 @@ end
 
 a = 200
 print(f()()) -- 100
-print(a)   -- 200
\ No newline at end of file
+print(a)   -- 200
+
+-- functions are stored into computed fields by assignment
+t = {}
+t[1] = function() return "declared by assignment" end
+print(t[1]()) -- declared by assignment
+
+
+-- arguments are evaluated in the caller's scope
+x = 1
+function id(a) return a end
+do
+    local x = 2
+    print(id(x)) -- 2
+end
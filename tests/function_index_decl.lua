@@ -0,0 +1,7 @@
+-- a function cannot be declared into a computed field,
+-- use `t[1] = function() ... end` instead (see function.lua):
+-- rua: tests/function_index_decl.lua:5:11: unexpected symbol near '['
+t = {}
+function t[1]()
+    return "unreachable"
+end
@@ -0,0 +1,16 @@
+-- parentheses truncate multiple results to one value
+function f() return 1, 2 end
+print((f())) -- 1
+print(f()) -- 1, 2
+
+local a, b = (f())
+print(a, b) -- 1, nil
+t = {(f())}
+print(#t) -- 1
+
+function g(...) return (...) end
+print(g(3, 4, 5)) -- 3
+
+-- no results become nil
+function none() end
+print((none())) -- nil
@@ -0,0 +1,12 @@
+-- hexadecimal numerals are integers, the digits may be in either case
+print(0xFF == 255, 0x10 == 16, 0xff, 0XaBc) -- true, true, 255, 2748
+print(0x0, 0x7fffffffffffffff) -- 0, 9223372036854775807
+print(0x10 + 1, 0x10 / 2) -- 17, 8.0
+
+-- strings with hexadecimal numerals are converted too
+print(tonumber("0x1F"), "0x10" + 0, tonumber("-0x10")) -- 31, 16, -16
+print(tonumber("0x"), tonumber("0xg")) -- nil, nil
+
+-- a fractional part or a binary exponent makes a hexadecimal float
+print(0x1.8p1, 0x.8, 0xA.8, 0x1P-2, 0x1p+4) -- 3.0, 0.5, 10.5, 0.25, 16.0
+print(tonumber("0x1.8p1"), "0x1p4" + 0, tonumber("0x1p")) -- 3.0, 16.0, nil
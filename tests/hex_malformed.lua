@@ -0,0 +1,3 @@
+-- a hexadecimal numeral with no digits, or followed by letters, is a lex error:
+-- rua: tests/hex_malformed.lua:3:5: malformed number near '0xg'
+a = 0xg
@@ -0,0 +1,11 @@
+-- an __index function may return several values, the field is the first of them
+t = setmetatable({}, {__index = function() return 10, 20 end})
+print(t.missing) -- 10
+print(select("#", t.missing), #{t.missing, t[1]}) -- 1, 2
+local a, b = t.missing
+print(a, b) -- 10, nil
+print(t.missing + 1, t["other"] .. "") -- 11, 10
+
+-- and none is nil
+e = setmetatable({}, {__index = function() end})
+print(e.missing, select("#", e.missing)) -- nil, 1
@@ -0,0 +1,33 @@
+-- a key absent from a table is looked up in the __index table of its metatable
+Base = {greet = function() return "hello" end, kind = "base"}
+Middle = setmetatable({kind = "middle"}, {__index = Base})
+obj = setmetatable({}, {__index = Middle})
+print(obj.kind, obj.greet()) -- middle, hello
+print(obj.missing) -- nil
+
+-- fields of the table itself come first
+obj.kind = "obj"
+print(obj.kind, Middle.kind) -- obj, middle
+
+-- prototype-based objects
+Account = {balance = 0}
+Account.__index = Account
+function Account.new(balance)
+    return setmetatable({balance = balance}, Account)
+end
+function Account:deposit(v)
+    self.balance = self.balance + v
+end
+a = Account.new(10)
+a:deposit(5)
+print(a.balance, Account.balance) -- 15, 0
+
+-- an __index function is called with the table and the key,
+-- only its first result is used
+t = setmetatable({}, {__index = function(t, k) return k .. "!", 20 end})
+print(t.x, t[1]) -- x!, 1!
+
+-- a chain of __index tables that loops is an error
+loop = {}
+setmetatable(loop, {__index = loop})
+print(pcall(function() return loop.x end)) -- false, tests/index_meta.lua:33: '__index' chain too long; possible loop
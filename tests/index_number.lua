@@ -0,0 +1,5 @@
+-- numbers cannot be indexed, unlike strings:
+-- rua: tests/index_number.lua:5: attempt to index a number value
+s = "abc"
+print(s.len == string.len) -- true
+print((5)[1])
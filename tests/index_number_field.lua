@@ -0,0 +1,4 @@
+-- rua: tests/index_number_field.lua:4: attempt to index a number value
+n = 5
+print(type(n))
+print(n.x)
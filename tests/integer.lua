@@ -0,0 +1,35 @@
+-- numbers are integers or floats, integers print without a decimal point
+print(10, 2.0, -0.0) -- 10, 2.0, -0.0
+print(10 / 2, 7 / 2) -- 5.0, 3.5
+print(7 // 2, 7.0 // 2, -7 // 2) -- 3, 3.0, -4
+print(2 ^ 2, 10 - 4.0, 3 * 2) -- 4.0, 6.0, 6
+print(7 % 3, 7.5 % 2, -7 % 3) -- 1, 1.5, 2
+print(-(3), -(3.0)) -- -3, -3.0
+
+-- floats are shown with 14 significant digits
+print(1 / 3, 2 ^ 53, 2 ^ -20) -- 0.33333333333333, 9.007199254741e+15, 9.5367431640625e-07
+print(100 / 1) -- 100.0
+
+-- integers and floats with the same value are equal, also as table keys
+print(1 == 1.0, 1 < 1.5, 2 <= 2.0) -- true, true, true
+t = {}
+t[1.0] = "one"
+t[2] = "two"
+print(t[1], t[2.0], #t) -- one, two, 2
+
+-- strings are converted following the syntax of numerals
+print("10" + 1, "10.0" + 1, " 5 " * 2) -- 11, 11.0, 10
+print(tonumber("10"), tonumber("10.0"), tonumber("ff", 16)) -- 10, 10.0, 255
+print(10 .. "", 1.5 .. "", 2.0 .. "") -- 10, 1.5, 2.0
+
+-- integer arithmetic wraps around
+print(9223372036854775807 + 1 == -9223372036854775807 - 1) -- true
+
+-- integer division by zero is an error, float division is not
+print(pcall(function() return 1 // 0 end)) -- false, tests/integer.lua:29: attempt to perform 'n//0'
+print(pcall(function() return 1 % 0 end)) -- false, tests/integer.lua:30: attempt to perform 'n%0'
+print(1 / 0, -1 // 0.0) -- inf, -inf
+
+-- math.floor and math.ceil give integers
+print(math.floor(3.7), math.ceil(3.2), math.floor(-0.0)) -- 3, 4, 0
+print(math.max(1, 2.5), math.max(3, 2.5), math.abs(-4)) -- 2.5, 3, 4
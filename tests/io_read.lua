@@ -0,0 +1,10 @@
+-- run with `rua tests/io_read.lua < tests/io_read.lua`, so the input is this file
+-- 12 3.5 -7 0x10 oops
+print(io.read()) -- -- run with `rua tests/io_read.lua < tests/io_read.lua`, so the input is this file
+print(io.read(2), io.read("n", "*n")) -- --, 12, 3.5
+print(io.read("n")) -- -7
+print(io.read("n"), io.read("n")) -- 16, nil, as "oops" is not a number and is left unread
+print(io.read("l")) -- oops
+print(io.read("L") == 'print(io.read()) -- -- run with `rua tests/io_read.lua < tests/io_read.lua`, so the input is this file\n') -- true
+print(io.read(0) == "", #io.read("a") > 0) -- true, true
+print(io.read("l"), io.read(1), io.read("a") == "", io.read(0)) -- nil, nil, true, nil
@@ -0,0 +1,7 @@
+-- io.write writes its arguments with nothing between or after them
+io.write("a", "b") io.write("c")
+io.write("\n") -- abc
+io.write(1, " ", 2.5, " ", 1.0, " ", 10 / 3, " ", 2^63, "\n") -- 1 2.5 1 3.3333333333333 9.2233720368548e+18
+io.write() io.write("x", "\n") -- x
+print(io.write("y")) -- y
+print("and print still ends its line") -- and print still ends its line
@@ -0,0 +1,3 @@
+-- io.write only takes strings and numbers:
+-- rua: tests/io_write_type.lua:3: bad argument #2 to 'write' (string expected, got table)
+io.write("a", {})
@@ -0,0 +1,33 @@
+-- the length of a table is a border: t[n] is not nil and t[n + 1] is nil
+print(#{10, 20, 30} == 3, #{}) -- true, 0
+print(#{nil}, #{nil, 2} == 0 or #{nil, 2} == 2) -- 0, true
+
+-- keys that are not positive integers do not count
+t = {1, 2, x = "x", y = "y"}
+t[0] = 0
+t[-1] = -1
+t[1.5] = 1.5
+print(#t) -- 2
+t[3.0] = 3
+print(#t) -- 3
+
+-- a table with a hole has several borders, any of them is a length
+t = {1, 2, 3, nil, 5}
+print(#t == 3 or #t == 5) -- true
+t = {}
+t[1], t[2], t[4] = 1, 2, 4
+print(#t == 2 or #t == 4) -- true
+
+-- removing the last element moves the border
+t = {1, 2, 3}
+t[#t] = nil
+print(#t) -- 2
+
+-- long sequences
+t = {}
+for i = 1, 1000 do
+    t[i] = i
+end
+print(#t) -- 1000
+t[1001] = 1001
+print(#t) -- 1001
@@ -0,0 +1,22 @@
+-- load compiles a string into a function, errors in it are reported at the lines
+-- of the chunk, in the chunk named by load's second argument:
+-- rua: config:2:10: attempt to perform arithmetic on nil value
+-- stack traceback:
+--     config:2: in main chunk
+--     tests/load.lua:22: in main chunk
+local f = load("return 2 * 21")
+print(f()) -- 42
+local g = load("x = 1\nlocal y = x + nil", "=config")
+print(pcall(g)) -- false, config:2: attempt to add number with nil
+
+-- without a name, the chunk is named after its source
+print(pcall(load("local t = {}\nreturn t.a.b"))) -- false, [string "local t = {}..."]:2: attempt to index a nil value
+
+-- the chunk runs among the globals, and a syntax error gives nil and the message
+print(x, load("return 1 +", "=bad")) -- 1, nil, bad:1:11: unexpected symbol near '<eof>'
+
+-- functions defined in the chunk report their errors in it too
+local h = load("local function fail()\n  error('in fail')\nend\nreturn fail", "@lib.lua")()
+print(pcall(h)) -- false, lib.lua:2: in fail
+
+load("local z = 1\nreturn 2 ^ nil", "=config")()
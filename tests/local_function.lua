@@ -0,0 +1,27 @@
+-- a local function can call itself
+local function fact(n)
+    if n <= 1 then
+        return 1
+    end
+    return n * fact(n - 1)
+end
+print(fact(5), fact(10)) -- 120, 3628800
+
+-- also when it is called after leaving the scope it is defined in
+function counter()
+    local function countdown(n)
+        if n == 0 then
+            return "liftoff"
+        end
+        return countdown(n - 1)
+    end
+    return countdown
+end
+print(counter()(3)) -- liftoff
+
+-- `local f = function` does not see itself, f in the body is the global one
+f = function() return "global" end
+do
+    local f = function() return f() end
+    print(f()) -- global
+end
@@ -0,0 +1,26 @@
+// the output of numbers does not depend on the locale of the host,
+// the decimal separator is always a period, as in the C locale
+use std::{
+    io::Write,
+    process::{Command, Stdio},
+};
+
+#[test]
+fn numbers_use_a_period_under_a_comma_locale() {
+    let mut rua = Command::new(env!("CARGO_BIN_EXE_rua"))
+        .arg("-")
+        .env("LC_ALL", "de_DE.UTF-8")
+        .env("LANG", "de_DE.UTF-8")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .expect("the rua binary should start");
+    let script = "print(string.format('%.2f', 1.5)) print(1.5) \
+                  print(string.format('%e %5.1f %q', 0.25, 1e6, 0.5)) io.write(2.5, '\\n')";
+    rua.stdin.take().unwrap().write_all(script.as_bytes()).unwrap();
+    let output = rua.wait_with_output().unwrap();
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "1.50\n1.5\n2.500000e-01 1000000.0 0x1p-1\n2.5\n"
+    );
+}
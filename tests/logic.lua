@@ -16,4 +16,29 @@ elseif a == 1 then
     print("hello, a==1")
 else 
     print("a ~= 1")
-end
\ No newline at end of file
+end
+
+-- only nil and false are falsy, unlike C or Python
+if 0 then print("0 is true") else print("0 is false") end -- 0 is true
+if "" then print("'' is true") else print("'' is false") end -- '' is true
+if {} then print("{} is true") else print("{} is false") end -- {} is true
+if nil then print("nil is true") else print("nil is false") end -- nil is false
+if false then print("false is true") else print("false is false") end -- false is false
+
+
+-- 'and' and 'or' return one of their operands, not a boolean
+print(nil or 5) -- 5
+print(false or "x") -- x
+print(3 and 4) -- 4
+print(nil and 4) -- nil
+print(false and nil) -- false
+print(1 or error_not_evaluated) -- 1
+
+-- the ternary idiom
+t = {name = "rua"}
+print((t and t.name) or "anon") -- rua
+t = nil
+print((t and t.name) or "anon") -- anon
+local x = nil
+x = x or "default"
+print(x) -- default
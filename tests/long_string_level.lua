@@ -0,0 +1,10 @@
+-- a long string can have a level, the number of '=' between its brackets
+print([==[contains ]] fine]==]) -- contains ]] fine
+print([=[a]]b]==]c]=], [[x]y]]) -- a]]b]==]c, x]y
+print([==[
+first newline is skipped]==]) -- first newline is skipped
+print(#[=[]=], [[]] == "") -- 0, true
+
+--[==[ a comment with a level,
+it can contain ]] too ]==]
+print("after comment") -- after comment
@@ -0,0 +1,3 @@
+-- a long string only ends at a closing bracket of the same level:
+-- rua: tests/long_string_unfinished.lua:4:1: unfinished long string (starting at line 3) near '<eof>'
+s = [==[ never closed ]=]
@@ -25,4 +25,124 @@ while a > 0 do
     if a < 4 then 
         break
     end
-end
\ No newline at end of file
+end
+
+--- numeric for with a negative step
+--- 5, 4, 3, 2, 1
+for i = 5, 1, -1 do
+    print(i)
+end
+
+--- fractional step, the loop variable is a float
+--- 0.0, 0.25, 0.5, 0.75, 1.0
+for i = 0, 1, 0.25 do
+    print(i)
+end
+
+--- the body is skipped if start is already past end
+for i = 1, 0 do
+    print("not printed")
+end
+for i = 0, 1, -1 do
+    print("not printed")
+end
+
+--- the step does not drift: 0.1 added up ten times is not exactly 1,
+--- but the last value is 0 + 10 * 0.1
+--- 11, 1.0
+n = 0
+for x = 0, 1, 0.1 do
+    n = n + 1
+    last = x
+end
+print(n)
+print(last)
+
+--- the bounds are evaluated once and the loop variable is fresh each iteration,
+--- so assigning to it does not change the iteration
+--- 1, 2, 3
+limit = 3
+for i = 1, limit do
+    limit = 10
+    print(i)
+    i = i * 100
+end
+
+--- the loop still runs three times when the body assigns to the variable
+--- 3
+count = 0
+for i = 1, 3 do
+    i = 100
+    count = count + 1
+end
+print(count)
+
+--- strings convertible to numbers are accepted
+--- 2, 1
+for i = "2", "1", "-1" do
+    print(i)
+end
+
+
+--- repeat loop, the body runs at least once
+--- 1, 2, 3
+n = 1
+repeat
+    print(n)
+    n = n + 1
+until n > 3
+
+--- 100
+repeat print(100) until true
+
+--- the condition sees the locals of the body
+--- 2, 4, 8
+x = 1
+repeat
+    local doubled = x * 2
+    print(doubled)
+    x = doubled
+until doubled >= 8
+
+--- break also ends a repeat loop
+--- 1
+repeat
+    print(1)
+    break
+until false
+
+
+--- removing fields while traversing a table is safe
+--- every field is visited once and the table ends up empty
+t = {a = 1, b = 2, c = 3}
+count = 0
+for k, v in pairs(t) do
+    t[k] = nil
+    count = count + 1
+end
+print(count, #t, t.a, t.b, t.c) -- 3, 0, nil, nil, nil
+
+--- updating existing fields is safe as well
+t = {x = 1, y = 2}
+for k, v in pairs(t) do
+    t[k] = v * 10
+end
+print(t.x, t.y) -- 10, 20
+
+--- integer loops count in integers, so they are exact beyond 2^53 and stop at the largest one
+count = 0
+for i = 9007199254740993, 9007199254740995 do
+    count = count + 1
+    last = i
+end
+print(count, last) -- 3, 9007199254740995
+count = 0
+for i = 9223372036854775805, 9223372036854775807 do
+    count = count + 1
+end
+print(count) -- 3
+
+--- a float limit is rounded towards the start
+for i = 1, 2.9 do io.write(i, " ") end
+for i = 3, 1.5, -1 do io.write(i, " ") end
+print() -- 1 2 3 2
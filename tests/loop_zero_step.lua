@@ -0,0 +1,3 @@
+-- a numeric for loop cannot have a zero step:
+-- rua: tests/loop_zero_step.lua:3: 'for' step is zero
+for i = 1, 10, 0 do print(i) end
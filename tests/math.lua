@@ -0,0 +1,18 @@
+print(math.floor(-1.5) == -2) -- true
+print(math.floor(3.7), math.floor("2.5")) -- 3, 2
+print(math.ceil(3.2), math.ceil(-0.5)) -- 4, 0
+print(math.max(3, 1, 7, 2) == 7) -- true
+print(math.min(3, 1, 7, 2), math.max(-1)) -- 1, -1
+print(math.sqrt(16), math.sqrt(2) ^ 2 - 2 < 0.000001) -- 4.0, true
+print(math.pi > 3.14159 and math.pi < 3.1416) -- true
+print(math.huge, -math.huge) -- inf, -inf
+print(math.huge > 10 ^ 300) -- true
+
+print(math.abs(-3), math.abs(2.5)) -- 3, 2.5
+print(type(math), type(math.floor)) -- table, function
+
+-- trigonometric and exponential functions
+print(math.cos(0) == 1, math.sin(0), math.tan(0)) -- true, 0.0, 0.0
+print(math.sin(math.pi / 2)) -- 1.0
+print(math.exp(0) == 1) -- true
+print(math.log(math.exp(2))) -- 2.0
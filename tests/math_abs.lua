@@ -0,0 +1,10 @@
+-- math.abs follows f64::abs for floats
+print(math.abs(-0.0), 1 / math.abs(-0.0)) -- 0.0, inf, so the result is +0.0
+nan = 0 / 0
+print(math.abs(nan) ~= math.abs(nan)) -- true
+print(math.abs(-math.huge), math.abs(-2.5)) -- inf, 2.5
+
+-- numeric strings are converted as the arithmetic operators do
+print(math.abs("-5"), math.abs(" -0x10 "), math.abs("-1.5")) -- 5, 16, 1.5
+print(math.abs("-5") == -("-5")) -- true
+print(pcall(math.abs, "five")) -- false, tests/math_abs.lua:10: bad argument #1 to 'abs' (number expected, got string)
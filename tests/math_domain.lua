@@ -0,0 +1,11 @@
+-- out of their domain, math.sqrt and math.log give nan or inf, as f64 does, instead of an error
+print(math.sqrt(-1) ~= math.sqrt(-1)) -- true, nan is not equal to itself
+print(math.sqrt(-0.0), math.sqrt(math.huge)) -- -0.0, inf
+print(math.log(0), math.log(-0.0)) -- -inf, -inf
+print(math.log(-1) ~= math.log(-1), math.log(math.huge)) -- true, inf
+
+-- an optional base, 2 and 10 being exact
+print(math.log(8, 2) == 3, math.log(8, 2)) -- true, 3.0
+print(math.log(1000, 10), math.log(2 ^ 50, 2)) -- 3.0, 50.0
+print(math.log(81, 3) - 4 < 1e-12, math.log(0, 2)) -- true, -inf
+print(pcall(math.log, 8, "two")) -- false, tests/math_domain.lua:11: bad argument #2 to 'log' (number expected, got string)
@@ -0,0 +1,25 @@
+-- setmetatable stores the metatable and returns the table itself
+t = {}
+mt = {}
+print(setmetatable(t, mt) == t) -- true
+print(getmetatable(t) == mt) -- true
+
+-- tables have no metatable by default, nil removes it
+print(getmetatable({})) -- nil
+setmetatable(t, nil)
+print(getmetatable(t)) -- nil
+print(getmetatable("abc"), getmetatable(1)) -- nil, nil
+
+-- a metatable can be shared
+a = setmetatable({}, mt)
+b = setmetatable({}, mt)
+print(getmetatable(a) == getmetatable(b)) -- true
+
+-- a __metatable field protects the metatable
+p = setmetatable({}, {__metatable = "locked"})
+print(getmetatable(p)) -- locked
+print(pcall(setmetatable, p, {})) -- false, tests/metatable.lua:21: cannot change a protected metatable
+
+-- the metatable must be a table or nil
+print(pcall(setmetatable, {}, 1)) -- false, tests/metatable.lua:24: bad argument #2 to 'setmetatable' (nil or table expected)
+print(pcall(setmetatable, 1, {})) -- false, tests/metatable.lua:25: bad argument #1 to 'setmetatable' (table expected, got number)
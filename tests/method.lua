@@ -0,0 +1,30 @@
+t = {a = {b = {}}}
+
+function t.f(x)
+    return x + 1
+end
+print(t.f(1)) -- 2
+
+function t.a.b.g()
+    return "nested"
+end
+print(t.a.b.g()) -- nested
+
+account = {balance = 0}
+function account:deposit(v)
+    self.balance = self.balance + v
+    return self
+end
+account:deposit(100)
+print(account.balance) -- 100
+print(account:deposit(50).balance) -- 150
+print(account.deposit(account, 1).balance) -- 151
+
+function t.a:name()
+    return self == t.a
+end
+print(t.a:name()) -- true
+
+s = {}
+function s:echo(x) return x end
+print(s:echo{1}[1], s:echo"str") -- 1, str
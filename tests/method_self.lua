@@ -0,0 +1,31 @@
+-- inside a method, self is the receiver, and assigning its fields changes the receiver
+Obj = {}
+function Obj:set(v) self.value = v end
+obj = setmetatable({}, {__index = Obj})
+obj:set(10)
+print(obj.value == 10, rawget(Obj, "value")) -- true, nil
+
+-- through longer chains of fields and indexes
+function Obj:nest(k, v)
+    self.inner = self.inner or {}
+    self.inner[k] = v
+    self["last"] = k
+end
+obj:nest("a", 1)
+obj:nest("b", 2)
+print(obj.inner.a + obj.inner.b, obj.last) -- 3, b
+
+-- closures made in a method keep its self
+function Obj:counter()
+    return function() self.value = self.value + 1 return self.value end
+end
+local tick = obj:counter()
+tick()
+print(tick(), obj.value) -- 12, 12
+
+-- self is a parameter like the others, a local of the method
+function Obj:rebind()
+    self = {value = 0}
+    return self.value
+end
+print(obj:rebind(), obj.value) -- 0, 12
@@ -0,0 +1,2 @@
+-- a module that returns nothing, counting how many times it runs
+required = (required or 0) + 1
@@ -0,0 +1,3 @@
+-- a module that returns false, so that it is not kept as loaded
+refused = (refused or 0) + 1
+return false
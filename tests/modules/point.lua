@@ -0,0 +1,3 @@
+-- a module that returns a table, and the arguments it runs with
+local name, file = ...
+return {name = name, file = file, x = 1, y = 2}
@@ -0,0 +1,19 @@
+-- forward declared locals, each assigned a function calling the other
+local isEven, isOdd
+isEven = function(n)
+    if n == 0 then return true end
+    return isOdd(n - 1)
+end
+isOdd = function(n)
+    if n == 0 then return false end
+    return isEven(n - 1)
+end
+print(isEven(4), isOdd(4), isOdd(7)) -- true, false, true
+print(load("return isEven")(), type(isEven)) -- nil, function, they are not globals
+
+-- the functions see the locals as they are when called, not when defined
+local ping, pong
+ping = function(n) if n > 0 then return pong(n - 1) end return "ping" end
+print(pcall(ping, 1)) -- false, tests/mutual_recursion.lua:16: attempt to call a nil value
+pong = function(n) if n > 0 then return ping(n - 1) end return "pong" end
+print(ping(3), ping(4)) -- pong, ping
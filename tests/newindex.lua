@@ -0,0 +1,36 @@
+-- a write to an absent key is passed to the __newindex function of the metatable
+log = {}
+t = setmetatable({}, {__newindex = function(t, k, v)
+    log[#log + 1] = k .. "=" .. v
+    rawset(t, k, v)
+end})
+t.x = 1
+t.y = 2
+print(log[1], log[2], #log) -- x=1, y=2, 2
+print(t.x, t.y) -- 1, 2
+
+-- existing keys are written directly
+t.x = 10
+print(t.x, #log) -- 10, 2
+
+-- a __newindex table receives the write instead
+store = {}
+proxy = setmetatable({}, {__newindex = store, __index = store})
+proxy.a = "a"
+print(rawget(proxy, "a"), store.a, proxy.a) -- nil, a, a
+
+-- read-only tables
+readonly = setmetatable({}, {__newindex = function(t, k)
+    error("attempt to update a read-only table", 2)
+end})
+print(pcall(function() readonly.k = 1 end)) -- false, tests/newindex.lua:26: attempt to update a read-only table
+print(rawget(readonly, "k")) -- nil
+
+-- rawset bypasses __newindex
+rawset(readonly, "k", 1)
+print(readonly.k) -- 1
+
+-- a chain of __newindex tables that loops is an error
+loop = {}
+setmetatable(loop, {__newindex = loop})
+print(pcall(function() loop.x = 1 end)) -- false, tests/newindex.lua:36: '__newindex' chain too long; possible loop
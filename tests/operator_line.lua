@@ -0,0 +1,45 @@
+-- an error in an expression spread over lines is reported
+-- at the line of the operator that fails, not where the statement starts
+local t = {}
+
+print(pcall(function()
+    local x = 1
+        +
+        nil
+end)) -- false tests/operator_line.lua:7: attempt to add number with nil
+
+print(pcall(function()
+    local s = "a" ..
+        {}
+end)) -- false tests/operator_line.lua:12: attempt to concatenate a table value
+
+print(pcall(function()
+    return 1 <
+        "2"
+end)) -- false tests/operator_line.lua:17: attempt to compare number with string
+
+print(pcall(function()
+    return -
+        {}
+end)) -- false tests/operator_line.lua:22: attempt to perform negate operation on a 'table'
+
+print(pcall(function()
+    return #
+        nil
+end)) -- false tests/operator_line.lua:27: attempt to get length of a nil value
+
+-- indexing fails at the '.' or '[' of the field
+print(pcall(function()
+    return t.missing
+        .field
+end)) -- false tests/operator_line.lua:34: attempt to index a nil value
+
+print(pcall(function()
+    return t
+        .missing[1]
+end)) -- false tests/operator_line.lua:39: attempt to index a nil value
+
+print(pcall(function()
+    t.missing
+        .field = 1
+end)) -- false tests/operator_line.lua:44: attempt to assign a nil value
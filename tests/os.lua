@@ -0,0 +1,17 @@
+-- os.time, os.clock and os.date, with dates in UTC
+print(os.time() > 0) -- true
+print(os.date("%Y", 0)) -- 1970
+print(os.date("%Y-%m-%d %H:%M:%S", 86400 * 365 + 3661)) -- 1971-01-01 01:01:01
+print(os.date("!%c", 0)) -- Thu Jan  1 00:00:00 1970
+print(os.date("%x %X %p %j %a %A %b %B %%", 0)) -- 01/01/70 00:00:00 AM 001 Thu Thursday Jan January %
+
+local t = os.date("*t", 951825600)
+print(t.year, t.month, t.day, t.hour, t.min, t.sec) -- 2000 2 29 12 0 0
+print(t.wday, t.yday, t.isdst) -- 3 60 false
+
+local c = os.clock()
+print(type(c), c >= 0) -- number true
+
+print(pcall(os.date, "%Q", 0)) -- false tests/os.lua:15: bad argument #1 to 'date' (invalid conversion specifier '%Q')
+print(pcall(os.time, {year = 2^60, month = 1, day = 1})) -- false tests/os.lua:16: field 'year' is out-of-bound
+print(pcall(os.time, {year = 2000, month = 1, day = 2^62})) -- false tests/os.lua:17: field 'day' is out-of-bound
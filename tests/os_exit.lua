@@ -0,0 +1,4 @@
+-- os.exit ends the script at once, after writing out what was printed
+io.write("buffered output is kept") -- buffered output is kept
+os.exit(3)
+print("not reached")
@@ -0,0 +1,5 @@
+-- os.getenv reads the environment of the process running the script
+print(type(os.getenv("PATH"))) -- string
+print(os.getenv("PATH") == os.getenv("PATH")) -- true
+print(os.getenv("RUA_SURELY_UNSET_VARIABLE")) -- nil
+print(pcall(os.getenv)) -- false tests/os_getenv.lua:5: bad argument #1 to 'getenv' (string expected, got no value)
@@ -0,0 +1,20 @@
+-- os.time of a date table is the number of seconds since 1970-01-01 00:00:00 UTC
+print(os.time({year = 1970, month = 1, day = 1, hour = 0})) -- 0
+print(os.time({year = 2024, month = 3, day = 1, hour = 0, min = 30, sec = 15})) -- 1709253015
+print(os.time({year = 1969, month = 12, day = 31, hour = 23, min = 59, sec = 59})) -- -1
+print(os.time({year = 2000, month = 1, day = 1, hour = 0, isdst = false})) -- 946684800
+
+-- the hour defaults to noon, and months past december carry into the year
+print(os.time({year = 2000, month = 2, day = 29})) -- 951825600
+print(os.time({year = 2000, month = 14, day = 1, hour = 0})) -- 980985600
+print(os.time({year = 2001, month = 0, day = 1, hour = 0})) -- 975628800
+print(os.time({year = 2000, month = 3, day = 0, hour = 0})) -- 951782400, february 29th
+
+-- it is the inverse of os.date("*t")
+print(os.time(os.date("*t", 1234567890))) -- 1234567890
+
+-- year, month and day are required, and fields must be integers
+print(pcall(os.time, {month = 1, day = 1})) -- false, tests/os_time.lua:17: field 'year' missing in date table
+print(pcall(os.time, {year = 2000, day = 1})) -- false, tests/os_time.lua:18: field 'month' missing in date table
+print(pcall(os.time, {year = 2000, month = 1})) -- false, tests/os_time.lua:19: field 'day' missing in date table
+print(pcall(os.time, {year = 2000, month = 1.5, day = 1})) -- false, tests/os_time.lua:20: field 'month' is not an integer
@@ -0,0 +1,45 @@
+-- pairs visits every pair of a mixed table once, in no particular order
+t = {10, 20, 30, x = "a", y = "b"}
+count, sum, s = 0, 0, ""
+for k, v in pairs(t) do
+    count = count + 1
+    if type(k) == "number" then
+        sum = sum + v
+    else
+        s = s .. k .. "=" .. v .. ";"
+    end
+end
+print(count, sum, #s) -- 5, 60, 8
+
+-- ipairs walks 1, 2, 3, ... and stops at the first hole
+--- 1 a, 2 b
+for i, v in ipairs({"a", "b", nil, "d"}) do
+    print(i, v)
+end
+for i, v in ipairs({}) do
+    print("not printed")
+end
+
+-- next returns the pair after a key, nil starts and ends the traversal
+t = {x = 1}
+print(next(t)) -- x, 1
+print(next(t, "x")) -- nil
+print(next({})) -- nil
+
+-- pairs and ipairs are plain functions
+print(type(pairs), type(ipairs), type(next)) -- function, function, function
+f, s, c = pairs(t)
+print(f == next, s == t, c) -- true, true, nil
+
+-- any function can drive a generic for
+function range(n)
+    return function(limit, i)
+        if i < limit then
+            return i + 1
+        end
+    end, n, 0
+end
+--- 1, 2, 3
+for i in range(3) do
+    print(i)
+end
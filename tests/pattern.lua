@@ -0,0 +1,41 @@
+-- string.gsub returns the new string and the number of replacements
+print(string.gsub("a,b,c", ",", ";")) -- a;b;c, 2
+print(string.gsub("hello", "l", "L", 1)) -- heLlo, 1
+print(string.gsub("hello", "x", "y")) -- hello, 0
+
+-- a function is called with the captures (or the whole match)
+print(string.gsub("hello world", "%a+", function(w) return string.upper(w) end)) -- HELLO WORLD, 2
+-- returning nil or false keeps the match
+print(string.gsub("a b c", "%a", function(c) if c == "b" then return "B" end end)) -- a B c, 3
+
+-- a table is indexed by the first capture
+print(("$name is $age"):gsub("%$(%w+)", {name = "rua", age = 3})) -- rua is 3, 2
+
+-- %1..%9 are the captures, %0 the whole match
+print(string.gsub("hello world", "(%w+) (%w+)", "%2 %1")) -- world hello, 1
+print(string.gsub("abc", "%w", "<%0>")) -- <a><b><c>, 3
+print(string.gsub("50", "%d+", "%0%%")) -- 50%, 1
+
+-- anchors, sets, lazy repetition, balanced strings and frontiers
+print(string.gsub("hello hello", "^hello", "bye")) -- bye hello, 1
+print(string.gsub("  trim me  ", "^%s*(.-)%s*$", "[%1]")) -- [trim me], 1
+print(string.gsub("a1b22c333", "[%d]+", "#")) -- a#b#c#, 3
+print(string.gsub("f(a(b)c) g()", "%b()", "")) -- f g, 2
+print(string.gsub("THE (quick) fox", "%f[%a]%a+", "w")) -- w (w) w, 3
+print(string.gsub("abc", "", "-")) -- -a-b-c-, 4
+
+-- string.gmatch iterates over the matches
+--- one, two, three
+for w in string.gmatch("one two, three", "%a+") do
+    print(w)
+end
+
+--- a 1, b 2
+for k, v in ("a=1, b=2"):gmatch("(%w+)=(%w+)") do
+    print(k, v)
+end
+
+-- the iterator can be called directly, it returns nil after the last match
+it = string.gmatch("x y", "%a")
+print(it(), it(), it()) -- x, y, nil
+print(type(it)) -- function
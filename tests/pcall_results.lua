@@ -0,0 +1,13 @@
+-- on success pcall returns true followed by all the results of the function
+local ok, a, b = pcall(function() return 1, 2 end)
+print(ok, a, b) -- true, 1, 2
+print(select("#", pcall(function() return 1, nil, 3, nil end))) -- 5
+print(pcall(function() return nil, "second" end)) -- true, nil, second
+print(select("#", pcall(function() end))) -- 1
+
+-- they expand where a call does, and are cut where it is
+local t = {pcall(function() return "a", "b", "c" end)}
+print(#t, t[1], t[4]) -- 4, true, c
+print((pcall(function() return 1, 2 end))) -- true
+local function pass(...) return ... end
+print(pcall(pass, "x", "y", "z")) -- true, x, y, z
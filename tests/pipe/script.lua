@@ -0,0 +1,8 @@
+-- a script read from stdin, run with `rua - < tests/pipe/script.lua`
+-- it runs as a whole, like a file, and its errors are reported as in stdin:
+-- rua: stdin:8: attempt to call a nil value
+local function greet(name)
+    return "hello, " .. name
+end
+print(greet("pipe")) -- hello, pipe
+missing()
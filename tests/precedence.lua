@@ -1,6 +1,11 @@
 print(5 * (1 + 2)^3 == 135) -- true
 print(true or nil and false) --- true
-print(200 / -20^2 == -0.5 and 0) -- true
+print(200 / -20^2 == -0.5 and 0) -- 0, "and" returns its second operand
 print(200 / -20^2 .. ", number") -- -0.5, number
 
-print(3 > 4 == 4) -- false
\ No newline at end of file
+print(3 > 4 == 4) -- false
+-- '^' is right associative and binds tighter than unary minus
+print(2^3^2 == 512) -- true
+print(-2^2 == -4) -- true
+print(2^-2 == 0.25) -- true
+print(2^-1^2) -- 0.5
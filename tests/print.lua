@@ -0,0 +1,10 @@
+-- print separates its arguments with tabs and ends the line right after the last one
+print(1, 2, 3) -- 1	2	3
+print("a", nil, false) -- a	nil	false
+print() -- an empty line
+print("") -- an empty line too
+print(nil) -- nil
+
+-- every value goes through tostring
+t = setmetatable({}, {__tostring = function() return "T" end})
+print(t, 1.5, 10 // 1) -- T	1.5	10
@@ -0,0 +1,7 @@
+-- print and io.write share one output, so their text comes in the order of the calls
+io.write("a") print("b") -- ab
+io.write("c") io.write(1, "\n") print("d") -- c1
+-- d
+for i = 1, 3 do io.write(i) print(i * 10) end -- 110
+-- 220
+-- 330
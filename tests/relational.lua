@@ -17,4 +17,19 @@ a = {1,2,3}
 b = {1,2,3}
 print(a == b)  -- false, they are not the same object
 print(a == a)  -- true, it is the same object
-print(123 == "123") -- false, the types are different
\ No newline at end of file
+print(123 == "123") -- false, the types are different
+
+print("--------------------")
+
+-- numbers are ordered by value, strings byte by byte
+print(-1 < 0, 0.5 <= 0.5, 10 > 9) -- true true true
+print("a" < "b", "Z" < "a", "abc" < "abd", "ab" < "abc") -- true true true true
+print("10" < "9") -- true, strings are not compared as numbers
+
+-- any ordering involving NaN is false
+nan = 0/0
+print(nan < 1, 1 < nan, nan <= nan, nan > 1, nan >= 1) -- false false false false false
+print(nan == nan, nan ~= nan) -- false true
+
+-- nil and booleans compare by value
+print(nil == nil, true == true, false ~= false, nil == false) -- true, true, false, false
@@ -0,0 +1,11 @@
+-- run with `rua < tests/repl/recover.lua`: an error on one line is reported, and
+-- the scopes and calls it was in are dropped, so the next lines run as usual
+do local hidden = 1; error("inside a block") end -- rua: stdin:1: inside a block
+hidden -- nil
+function depth(n) if n == 0 then error("at the bottom") end return 1 + depth(n - 1) end
+depth(450) -- rua: stdin:1: at the bottom, and the traceback of the 450 calls
+function depth(n) if n == 0 then return 0 end return 1 + depth(n - 1) end
+depth(450) -- 450, the calls of the failed line do not count
+count = 0
+for i = 1, 3 do count = count + i end
+count -- 6
@@ -0,0 +1,16 @@
+-- statements for the interactive mode, run with `rua < tests/repl/session.lua`
+-- the values of an expression are printed, errors are reported and the session goes on
+x = 1 + 1
+x -- 2
+error("boom") -- rua: stdin:1: boom
+print(x * 10) -- 20
+local t = {}
+t.y = nil + 1 -- rua: stdin:1:11: attempt to add nil with number
+function f(a)
+  return a, a * 2
+end
+f(21) -- 21, 42
+x, #"abc" -- 2, 3
+print(nil) -- nil
+= 3 -- rua: stdin:1:1: unexpected symbol '='
+"done" -- done
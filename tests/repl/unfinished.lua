@@ -0,0 +1,5 @@
+-- run with `rua < tests/repl/unfinished.lua`: the input ends inside a chunk, whose error
+-- is reported before the session ends, at the line in the chunk: rua: stdin:3:1: unexpected token '<eof>', expect 'end'
+"complete" -- complete
+function unfinished()
+  return 1
@@ -0,0 +1,22 @@
+-- require runs a module once, and keeps its value in package.loaded
+print(require("tests.modules.counter")) -- true, ./tests/modules/counter.lua
+print(required) -- 1
+print(require("tests.modules.counter"), required) -- true, 1
+print(package.loaded["tests.modules.counter"]) -- true
+
+-- a module returning false is kept as false, so it runs again
+print(require("tests.modules.no"), refused) -- false, 1
+print(package.loaded["tests.modules.no"]) -- false
+print(require("tests.modules.no"), refused) -- false, 2
+
+-- a module runs with its name and file name
+local point = require("tests.modules.point")
+print(point.name, point.file, point.x + point.y) -- tests.modules.point, ./tests/modules/point.lua, 3
+print(require("tests.modules.point") == point) -- true
+
+-- the libraries are loaded modules too
+print(package.loaded.string == string, require("math") == math) -- true, true
+print(package.path) -- ./?.lua
+
+print(pcall(require, "tests.modules.missing")) -- false, tests/require.lua:21: module 'tests.modules.missing' not found:
+--     no file './tests/modules/missing.lua'
@@ -0,0 +1,32 @@
+-- select('#', ...) counts the extra arguments, nil included
+function count(...)
+    return select("#", ...)
+end
+print(count()) -- 0
+print(count(1, nil, 3)) -- 3
+print(count(nil, nil)) -- 2
+
+-- select(n, ...) returns the arguments from the n-th on
+print(select(1, "a", "b", "c")) -- a, b, c
+print(select(2, "a", "b", "c")) -- b, c
+print(select(3, "a", "b", "c")) -- c
+print(select(4, "a", "b", "c")) -- (nothing)
+print(select("2", "a", "b")) -- b
+
+-- a negative n counts from the end
+print(select(-1, "a", "b", "c")) -- c
+print(select(-3, "a", "b", "c")) -- a, b, c
+
+-- walking the arguments of a variadic function
+function sum(...)
+    local total = 0
+    for i = 1, select("#", ...) do
+        total = total + select(i, ...)
+    end
+    return total
+end
+print(sum(1, 2, 3, 4)) -- 10
+
+print(pcall(select, 0, "a")) -- false tests/select.lua:30: bad argument #1 to 'select' (index out of range)
+print(pcall(select, -2, "a")) -- false tests/select.lua:31: bad argument #1 to 'select' (index out of range)
+print(pcall(select, "x")) -- false tests/select.lua:32: bad argument #1 to 'select' (number expected, got string)
@@ -0,0 +1,6 @@
+#!/usr/bin/env rua
+-- a shebang on the first line is skipped, the lines after it keep their numbers:
+-- rua: tests/shebang.lua:6: line 6
+local t = {1, 2, 3}
+print(#t, #"ab") -- 3, 2
+error("line 6")
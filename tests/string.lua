@@ -18,4 +18,12 @@ print("programming\nlanguage")
 print("programming\tlanguage") 
 print("programming\\language") 
 print("programming\'language")
-print("programming\"language")
\ No newline at end of file
+print("programming\"language")
+
+-- the length of a string is its number of bytes, not characters
+print(#"hello") -- 5
+print(#"héllo") -- 6, 'é' takes 2 bytes in UTF-8
+print(#"") -- 0
+
+-- '..' is right associative
+print("a" .. "b" .. "c" .. 1 .. 2) -- abc12
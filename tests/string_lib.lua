@@ -0,0 +1,40 @@
+print(string.len("hello"), string.len(""), string.len(123)) -- 5, 0, 3
+
+-- string.sub uses byte positions, negative ones count from the end
+print(string.sub("hello", 2, 4)) -- ell
+print(string.sub("hello", -3)) -- llo
+print(string.sub("hello", 2)) -- ello
+print(string.sub("hello", -100, 2)) -- he
+print(string.sub("hello", 0)) -- hello
+print(string.sub("hello", 3, 100)) -- llo
+print(string.sub("hello", -2, -1)) -- lo
+print(string.sub("hello", 4, 2) == "") -- true
+print(string.sub("hello", 6) == "", string.sub("hello", 1, -6) == "") -- true, true
+
+print(string.upper("Hello, World 1")) -- HELLO, WORLD 1
+print(string.lower("Hello, World 1")) -- hello, world 1
+
+print(string.rep("ab", 3)) -- ababab
+print(string.rep("ab", 3, ", ")) -- ab, ab, ab
+print(string.rep("ab", 0) == "", string.rep("ab", -1) == "") -- true, true
+
+print(string.reverse("hello")) -- olleh
+print(string.reverse("") == "") -- true
+-- both work on bytes, so they may split a multi-byte character
+s = "h\195\169llo"
+print(#s:sub(2, 2), s:sub(2, 2) ~= s:sub(3, 3), s:sub(2, 3) == "\195\169") -- 1, true, true
+print(#s:reverse(), s:reverse():reverse() == s, s:reverse():sub(4, 4) == "\169") -- 6, true, true
+
+-- string.len and '#' both count bytes
+strings = {"", "hello", "héllo", "日本語", "a\tb"}
+for i = 1, #strings do
+    s = strings[i]
+    print(string.len(s) == #s, #s) -- true, 0 / 5 / 6 / 9 / 3
+end
+
+-- strings have the string library as methods
+s = "Hello"
+print(s:upper(), s:len(), ("abc"):rep(2)) -- HELLO, 5, abcabc
+print(s:sub(2, 3), s.lower(s)) -- el, hello
+print(("x = %d"):format(3)) -- x = 3
+print(s.foo) -- nil
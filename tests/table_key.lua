@@ -0,0 +1,13 @@
+-- nil and NaN cannot be table keys
+t = {}
+print(pcall(function() t[nil] = 1 end)) -- false, tests/table_key.lua:3: table index is nil
+print(pcall(function() t[0/0] = 1 end)) -- false, tests/table_key.lua:4: table index is NaN
+print(pcall(rawset, t, nil, 1)) -- false, tests/table_key.lua:5: table index is nil
+
+-- but reading them gives nil
+print(t[nil], t[0/0]) -- nil, nil
+print(next(t)) -- nil
+
+-- the error is raised before __newindex is consulted
+logged = setmetatable({}, {__newindex = function() print("not printed") end})
+print(pcall(function() logged[nil] = 1 end)) -- false, tests/table_key.lua:13: table index is nil
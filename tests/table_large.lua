@@ -0,0 +1,14 @@
+-- mutating a large table in a loop, each access shares the table instead of copying it
+t = {}
+n = 20000
+for i = 1, n do
+    t[i] = i
+end
+for i = 1, n do
+    t[i] = t[i] * 2
+end
+sum = 0
+for i = 1, n do
+    sum = sum + t[i]
+end
+print(#t, sum) -- 20000, 400020000
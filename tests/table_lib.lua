@@ -0,0 +1,50 @@
+-- table.insert appends by default, or inserts at a position shifting the elements up
+t = {1, 2, 3}
+table.insert(t, 4)
+print(t[1], t[2], t[3], t[4]) -- 1, 2, 3, 4
+table.insert(t, 2, "x")
+print(t[1], t[2], t[3], t[4], t[5]) -- 1, x, 2, 3, 4
+table.insert(t, 6, "end")
+print(t[6]) -- end
+
+-- table.remove removes the last element by default, or shifts the elements down
+t = {"a", "b", "c", "d"}
+print(table.remove(t, 1)) -- a
+print(t[1], t[2], t[3], t[4]) -- b, c, d, nil
+print(table.remove(t)) -- d
+print(t[1], t[2], t[3]) -- b, c, nil
+print(table.remove({})) -- nil
+
+-- table.concat joins the strings and numbers of the sequence
+print(table.concat({1, 2, 3}, "-") == "1-2-3") -- true
+print(table.concat({"a", "b", "c"})) -- abc
+print(table.concat({"a", "b", "c", "d"}, ", ", 2, 3)) -- b, c
+print(table.concat({}, ",") == "") -- true
+
+-- '#' is the position table.insert appends after
+t = {1, 2, 3}
+print(#t) -- 3
+table.insert(t, 4)
+print(t[4], #t) -- 4, 4
+table.insert(t, 1, 0)
+print(t[1], t[5], #t) -- 0, 4, 5
+table.remove(t)
+print(#t) -- 4
+
+-- table.sort sorts in place by '<', or by a comparison function
+t = {5, 2, 4, 1, 3}
+table.sort(t)
+print(t[1], t[2], t[3], t[4], t[5]) -- 1, 2, 3, 4, 5
+table.sort(t, function(a, b) return a > b end)
+print(t[1], t[2], t[3], t[4], t[5]) -- 5, 4, 3, 2, 1
+-- a function that is not an order is noticed
+print(pcall(table.sort, {3, 1, 2, 5, 4}, function(a, b) return true end)) -- false, tests/table_lib.lua:41: invalid order function for sorting
+print(pcall(table.sort, {1, "x", 3})) -- false, tests/table_lib.lua:42: attempt to compare string with number
+
+-- numbers sort in numeric order, integers and floats mixed, and strings in byte order
+t = {10, 2.5, -3, 7, 0, 2}
+table.sort(t)
+print(table.concat(t, " ")) -- -3 0 2 2.5 7 10
+t = {"b", "B", "abc", "ab", "", "a"}
+table.sort(t)
+print(table.concat(t, ",")) -- ,B,a,ab,abc,b
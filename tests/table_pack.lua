@@ -0,0 +1,27 @@
+-- table.pack stores its arguments and their number in n
+t = table.pack("a", "b", "c")
+print(t.n, t[1], t[2], t[3]) -- 3, a, b, c
+print(table.pack().n) -- 0
+
+-- trailing nils count in n
+t = table.pack(1, nil, 3, nil)
+print(t.n, t[1], t[2], t[3], t[4]) -- 4, 1, nil, 3, nil
+
+-- table.unpack returns t[i] to t[j], by default the whole sequence
+print(table.unpack({10, 20, 30})) -- 10, 20, 30
+print(table.unpack({10, 20, 30}, 2)) -- 20, 30
+print(table.unpack({10, 20, 30}, 2, 5)) -- 20, 30, nil, nil
+print(table.unpack({10, 20, 30}, 3, 1)) -- (nothing)
+print(table.unpack({}, -1, 1)) -- nil, nil, nil
+
+-- packing and unpacking round trips, with n to keep the nils
+print(table.unpack(table.pack(1, 2, 3))) -- 1, 2, 3
+t = table.pack(1, nil, 3)
+print(table.unpack(t, 1, t.n)) -- 1, nil, 3
+print(select("#", table.unpack(t, 1, t.n))) -- 3
+
+-- unpacked values expand as arguments
+print(math.max(table.unpack({4, 9, 2}))) -- 9
+
+print(pcall(table.unpack, {}, 1, 1e8)) -- false tests/table_pack.lua:26: too many results to unpack
+print(pcall(table.unpack)) -- false tests/table_pack.lua:27: bad argument #1 to 'unpack' (table expected, got no value)
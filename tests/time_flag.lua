@@ -0,0 +1,10 @@
+-- run with `rua --time tests/time_flag.lua`: the output below is unchanged on stdout,
+-- and three lines with the lex, parse and interpret durations are printed to stderr:
+-- lex:       ...
+-- parse:     ...
+-- interpret: ...
+sum = 0
+for i = 1, 100 do
+    sum = sum + i
+end
+print(sum) -- 5050
@@ -0,0 +1,8 @@
+-- run with `rua --tokens tests/tokens.lua`: the tokens are printed with their lines, one per line:
+-- (8, a)
+-- (8, =)
+-- (8, 1)
+-- (8, +)
+-- (8, 2)
+-- (9, <eof>)
+a = 1 + 2
@@ -0,0 +1,26 @@
+print(tostring(12)) -- 12
+print(tostring(nil), tostring(true)) -- nil, true
+print(tostring("s") == "s") -- true
+print(tostring({})) -- table: 0x...
+print(tostring(function() end)) -- function: 0x... (defined at line 5)
+print(tostring(print)) -- function: builtin: print
+print(type(tostring({}))) -- string
+
+-- lua functions show the line they are defined at
+local function f()
+end
+g = function()
+end
+print((string.gsub(tostring(f), "^function: 0x%x+ ", ""))) -- (defined at line 10)
+print((string.gsub(tostring(g), "^function: 0x%x+ ", ""))) -- (defined at line 12)
+
+print(tonumber("  42 ")) -- 42
+print(tonumber("0.5") + 1) -- 1.5
+print(tonumber(7)) -- 7
+print(tonumber("ff", 16) == 255) -- true
+print(tonumber("-101", 2)) -- -5
+print(tonumber("zz", 36)) -- 1295
+print(tonumber("xyz") == nil, tonumber("xyz")) -- true, nil
+print(tonumber("8", 8)) -- nil
+print(tonumber({})) -- nil
+print("  10 " + 1) -- 11
@@ -0,0 +1,39 @@
+-- an uncaught error lists the functions it was raised in, the innermost first,
+-- each at the line it was running:
+-- rua: tests/traceback.lua:22:14: attempt to add nil with number
+-- stack traceback:
+--     tests/traceback.lua:22: in function 'third'
+--     tests/traceback.lua:26: in field 'second'
+--     tests/traceback.lua:30: in method 'first'
+--     tests/traceback.lua:35: in function 'run'
+--     tests/traceback.lua:38: in function <tests/traceback.lua:38>
+--     tests/traceback.lua:39: in main chunk
+
+-- an error caught by pcall has no traceback
+local function fails()
+    error("caught")
+end
+print(pcall(fails)) -- false tests/traceback.lua:14: caught
+
+lib = {}
+obj = {}
+
+function third(x)
+    return x + 1
+end
+
+function lib.second(x)
+    return third(x) * 2
+end
+
+function obj:first(x)
+    return lib.second(x)
+end
+
+print("three calls deep") -- three calls deep
+local run = function()
+    return obj:first(nil)
+end
+-- a function without a name shows where it is defined
+handlers = {function() run() end}
+handlers[1]()
@@ -0,0 +1,9 @@
+print(type(1)) -- number
+print(type("s")) -- string
+print(type({})) -- table
+print(type(print)) -- function
+print(type(type)) -- function
+print(type(function() end)) -- function
+print(type(nil)) -- nil
+print(type(true)) -- boolean
+print(type(type(1))) -- string
@@ -0,0 +1,3 @@
+-- a string literal holds any UTF-8 text, and byte escapes can spell it too
+print("héllo", #"é") -- héllo, 2
+print("\xC3\xA9" == "é") -- true
@@ -0,0 +1,3 @@
+-- a string literal must be valid UTF-8, a stray byte like 0xFF is a lex error:
+-- rua: tests/utf8_invalid.lua:3:5: invalid UTF-8 sequence
+s = "ab"
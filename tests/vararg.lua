@@ -0,0 +1,39 @@
+-- '...' collects the extra arguments of a variadic function
+function f(a, ...)
+    return ...
+end
+print(f(1, 2, 3)) -- 2, 3
+print(f(1)) -- (nothing)
+
+x, y, z = f(0, "a", "b")
+print(x, y, z) -- a, b, nil
+
+-- '...' can be the whole parameter list, and builds tables
+function pack(...)
+    return {...}
+end
+t = pack(10, 20, 30)
+print(#t, t[1], t[2], t[3]) -- 3, 10, 20, 30
+
+-- only the last expression of a list expands to all values
+function g(...)
+    return ..., "last"
+end
+print(g(1, 2)) -- 1, last
+
+-- missing fixed parameters are nil and '...' is empty
+function h(a, b, ...)
+    local rest = {...}
+    return a, b, #rest
+end
+print(h(1)) -- 1, nil, 0
+print(h(1, 2, 3, 4)) -- 1, 2, 2
+
+-- the arguments are forwarded to another call
+function sum(a, b, c)
+    return a + b + c
+end
+function forward(...)
+    return sum(...)
+end
+print(forward(1, 2, 3)) -- 6
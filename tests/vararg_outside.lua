@@ -0,0 +1,5 @@
+-- '...' can only be used inside a variadic function:
+-- rua: tests/vararg_outside.lua:4:12: cannot use '...' outside a vararg function near '...'
+function f(a)
+    return ...
+end
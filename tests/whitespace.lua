@@ -0,0 +1,3 @@
+-- vertical tab and form feed are whitespace, like space and tab
+a=1+2
+print(a) -- 3